@@ -1,9 +1,9 @@
-use crate::config::Config;
+use crate::commands::selection::Selection;
+use crate::config::{Config, include};
 use crate::fst::dot::*;
 use crate::fst::*;
-use anyhow::{Context, Result, anyhow, ensure};
+use anyhow::{Context, Result, anyhow, bail, ensure};
 use clap::Parser;
-use std::fs;
 use std::io::Cursor;
 use std::path::PathBuf;
 
@@ -15,6 +15,8 @@ pub struct Dot {
   /// Path for the image. `dot` command is required.
   #[structopt()]
   image: PathBuf,
+  #[command(flatten)]
+  selection: Selection,
 }
 
 impl Dot {
@@ -28,34 +30,21 @@ impl Dot {
   }
 
   fn run(&self) -> Result<()> {
-    let yaml = fs::read_to_string(self.config.as_path())
-      .with_context(|| anyhow!("Can't read the config file: {}", self.config.display()))?;
+    let yaml = include::read(self.config.as_path())?;
 
     let mut config = Config::from_str(yaml.as_str())
       .with_context(|| anyhow!("Can't read the config file {}", self.config.display()))?;
+    self.selection.apply(&mut config)?;
 
-    let mut graph = TaskFst::new();
-    for task in config.tasks_values_mut() {
-      task.set_state(graph.add_state(task.id()));
-    }
+    let graph = TaskFst::from_config(&mut config, &[])?;
 
-    for task in config.tasks().values() {
-      if task.depends_on().len() == 0 {
-        graph.add_start_state(task.state());
-      } else {
-        for prev in task.depends_on().iter() {
-          let err_msg = format!("{} depends on {} but does not exists", task.id(), prev);
-          let prev_state = config.tasks().get(prev).ok_or(anyhow!(err_msg))?.state();
-          graph.add_arc(prev_state, task.state());
-        }
-      }
+    if let Some(cycle) = graph.find_cycle() {
+      bail!(
+        "Can't execute your configuration. There is a deadlock in your tasks: {}",
+        cycle.join(" -> ")
+      );
     }
 
-    ensure!(
-      !graph.is_cyclic(),
-      "Can't execute your configuration. There is a deadlock in your tasks !"
-    );
-
     let mut buf: Vec<u8> = vec![];
     dot_write_file(&graph, &mut buf).with_context(|| "Can't create dot file")?;
 
@@ -1,14 +1,20 @@
-use crate::config::Config;
+use crate::commands::run::parse_var;
+use crate::config::{Config, StepExit, Task, TaskExit, include};
+use crate::utils::deadline::Deadline;
+use crate::utils::logs::resolve_output;
+use crate::utils::terminal;
 use crate::utils::traits::{CommandConfig, WaitSchedule};
-use anyhow::{Context, Result, anyhow, ensure};
+use anyhow::{Context, Result, anyhow, bail, ensure};
 use chrono::Local;
 use clap::Parser;
 use cron::Schedule;
 use libc::{SIG_IGN, SIGHUP};
 use libc::{fork, signal};
-use std::fs;
-use std::path::PathBuf;
-use std::process::{Command, Stdio, exit};
+use std::io::{self, IsTerminal};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio, exit};
+use std::time::Instant;
 use tokio::runtime::Runtime;
 
 #[derive(Parser, Debug)]
@@ -27,6 +33,10 @@ pub struct Exec {
   /// Schedule your tasks using cron expression.
   #[arg(long = "cron")]
   cron: Option<Schedule>,
+  /// Set the variable used by `{{ key }}` templates, it overrides the `vars` of the configuration
+  /// and the environment variables. Can be used many times to set many variables.
+  #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var, number_of_values = 1)]
+  vars: Vec<(String, String)>,
   /// Command to execute
   #[arg()]
   command: Vec<String>,
@@ -73,7 +83,7 @@ impl Exec {
 
   fn run(&self) -> Result<()> {
     let rt = Runtime::new()?;
-    let (config, path) = if Some(PathBuf::from("-")) == self.config {
+    let (mut config, path) = if Some(PathBuf::from("-")) == self.config {
       (Config::default(), format!("-"))
     } else if let Some(path) = &self.config {
       self.config_path(path)?
@@ -83,32 +93,74 @@ impl Exec {
       (Config::default(), format!("<No Config File Path>"))
     };
 
-    let task = if let Some(task) = &self.task {
-      config
-        .tasks()
-        .get(task)
-        .ok_or(anyhow!(
-          "The task `{}` does not exist in your config file `{}`",
-          task,
-          path
-        ))?
-        .clone()
+    let task = self.task(&mut config, &path)?;
+    let id = if task.id().is_empty() {
+      "exec"
     } else {
-      crate::config::Task::new(format!(""), vec![self.command.join(" ")], vec![], None)
+      task.id().as_str()
     };
-    let cmd_line = task.full_command();
-
-    let mut child = Command::new("sh")
-      .arg("-c")
-      .arg(&cmd_line)
-      .stdin(self.stdin())
-      .stdout_opt(config.stdout(), !self.background)?
-      .stderr_opt(config.stderr(), !self.background)?
-      .working_dir(config.working_dir())?
-      .spawn()
-      .with_context(|| format!("Can't run command `{}`", cmd_line))?;
-
-    let exit = child.wait().unwrap();
+    // One log file for all the steps and attempts
+    let now = Local::now();
+    let stdout = resolve_output(config.task_stdout(&task), id, now);
+    let stderr = resolve_output(config.task_stderr(&task), id, now);
+    let mut attempt = 1;
+    let exit = loop {
+      let mut deadline = Deadline::new(config.task_timeout(&task), config.task_grace_period(&task));
+      let mut steps = vec![];
+      let status = loop {
+        let argv = config.task_argv(&task, config.task_runs_steps(&task).then_some(steps.len()));
+        let Some((program, args)) = argv.split_first() else {
+          bail!("The task {} has no command to run", id);
+        };
+        let cmd_line = argv.join(" ");
+        let started = Instant::now();
+        let foreground = !self.background && io::stdin().is_terminal();
+        let child = Command::new(program)
+          .args(args)
+          .stdin(self.stdin())
+          .stdout_opt(&stdout, !self.background)?
+          .stderr_opt(&stderr, !self.background)?
+          .working_dir(config.task_working_dir(&task))?
+          .task_env(&config, &task)?
+          .task_group(foreground)
+          .spawn();
+
+        let status = match child {
+          Ok(child) => {
+            let status = deadline.wait(child);
+            if foreground {
+              // The task took the terminal, runtasktic takes it back
+              let _ = terminal::foreground();
+            }
+            status.with_context(|| format!("Can't wait for command `{}`", cmd_line))?
+          }
+          Err(e) => {
+            // Like a shell, the task fails with 127 and can be retried
+            eprintln!("Can't run command `{}`: {}", cmd_line, e);
+            ExitStatus::from_raw(127 << 8)
+          }
+        };
+        if !config.task_runs_steps(&task) {
+          break status;
+        }
+        steps.push(StepExit::new(status, started.elapsed()));
+        if task.next_step(&steps).is_none() || deadline.timed_out() || deadline.interrupted() {
+          break status;
+        }
+      };
+      let exit = TaskExit::new(status, deadline.timed_out(), attempt)
+        .with_interrupted(deadline.interrupted())
+        .with_steps(&task, steps);
+
+      match task.retry() {
+        Some(retry) if retry.should_retry(&exit, attempt) && !deadline.interrupted() => {
+          std::thread::sleep(retry.delay_after(attempt));
+          attempt += 1;
+        }
+        _ => break exit,
+      }
+    };
+
     if let Some(notification) = config.notification().clone() {
       rt.block_on(notification.notify_task_end(&task, exit));
     }
@@ -116,6 +168,30 @@ impl Exec {
     Ok(())
   }
 
+  /// The task to execute, from the configuration or from the command line. The variables of the
+  /// configuration are resolved like with `run`.
+  fn task(&self, config: &mut Config, path: &str) -> Result<Task> {
+    if let Some(task) = &self.task {
+      ensure!(
+        config.tasks().contains_key(task),
+        "The task `{}` does not exist in your config file `{}`",
+        task,
+        path
+      );
+    }
+    // Other tasks may use variables which are not set, they are not executed
+    config.retain_tasks(|id| Some(id) == self.task.as_ref());
+    let overrides = self.vars.iter().cloned().collect();
+    config
+      .resolve_vars(&overrides)
+      .with_context(|| format!("Can't process the config file {}", path))?;
+
+    Ok(match &self.task {
+      Some(task) => config.tasks()[task].clone(),
+      None => Task::new(format!(""), vec![self.command.join(" ")], vec![], None),
+    })
+  }
+
   fn stdin(&self) -> Stdio {
     if self.background {
       Stdio::null()
@@ -135,9 +211,8 @@ impl Exec {
     }
   }
 
-  fn config_path(&self, path: &PathBuf) -> Result<(Config, String)> {
-    let yaml = fs::read_to_string(&path)
-      .with_context(|| anyhow!("Can't read the config file {}", &path.display()))?;
+  fn config_path(&self, path: &Path) -> Result<(Config, String)> {
+    let yaml = include::read(path)?;
 
     let config = Config::from_str(yaml.as_str())
       .with_context(|| anyhow!("Can't process the config file {}", &path.display()))?;
@@ -145,3 +220,38 @@ impl Exec {
     Ok((config, format!("{}", path.display())))
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const VARS_YAML: &str = "tests/resources/vars.yml";
+
+  fn load(args: &[&str]) -> Result<(Exec, Config, String)> {
+    let exec = Exec::try_parse_from([&["exec", "--config", VARS_YAML], args].concat())?;
+    let (config, path) = exec.config_path(Path::new(VARS_YAML))?;
+    Ok((exec, config, path))
+  }
+
+  #[test]
+  fn task_vars() -> Result<()> {
+    let (exec, mut config, path) = load(&["--task", "deploy", "--var", "target=prod"])?;
+    let task = exec.task(&mut config, &path)?;
+    assert_eq!(task.full_command(), "echo deploy 1.2.0 to prod");
+    assert_eq!(
+      config.task_working_dir(&task),
+      &Some("/tmp/runtasktic".to_string())
+    );
+
+    let (exec, mut config, path) = load(&["--task", "deploy"])?;
+    let err = exec.task(&mut config, &path).unwrap_err();
+    assert_eq!(
+      format!("{:#}", err),
+      format!(
+        "Can't process the config file {}: Unresolved variables: `target` in tasks.deploy.commands.0",
+        VARS_YAML
+      )
+    );
+    Ok(())
+  }
+}
@@ -2,7 +2,9 @@ use crate::commands::completion::Completion;
 use crate::commands::dot::Dot;
 use crate::commands::exec::Exec;
 use crate::commands::run::Run;
+use crate::commands::schema::Schema;
 use crate::commands::update::Update;
+use crate::commands::validate::Validate;
 use anyhow::Result;
 use clap::Parser;
 
@@ -10,7 +12,10 @@ mod completion;
 mod dot;
 mod exec;
 mod run;
+mod schema;
+mod selection;
 mod update;
+mod validate;
 
 #[derive(Parser, Debug)]
 pub enum Command {
@@ -27,6 +32,16 @@ pub enum Command {
   /// Inherit the notification from a configuration file and set your default one in your home: `~/.runtasktic.yml` or `~/.runtasktic.yaml`.
   #[command(name = "exec")]
   Exec(Exec),
+  /// Check your configuration files and report their errors and warnings with their line.
+  ///
+  /// Finds unknown keys, missing dependencies, deadlocks, unreachable tasks, empty commands, missing directories and bad notification settings.
+  #[command(name = "validate")]
+  Validate(Validate),
+  /// Print the JSON Schema of the configuration files.
+  ///
+  /// Use it in your editor to autocomplete and validate your configuration files.
+  #[command(name = "schema")]
+  Schema(Schema),
   /// Generate completion script for your shell.
   #[command(name = "completion", subcommand)]
   Completion(Completion),
@@ -41,6 +56,8 @@ impl Command {
       Command::Run(executable) => executable.exec(),
       Command::Exec(executable) => executable.exec(),
       Command::Dot(executable) => executable.exec(),
+      Command::Validate(executable) => executable.exec(),
+      Command::Schema(executable) => executable.exec(),
       Command::Completion(executable) => executable.exec(),
       Command::Update(executable) => executable.exec(),
     }
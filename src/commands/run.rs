@@ -1,16 +1,29 @@
-use crate::config::{Config, OnFailure};
+use crate::commands::selection::Selection;
+use crate::config::{
+  CommandArgs, Config, OnFailure, OnSkip, OutputMode, Resume, StepExit, Task, TaskExit, include,
+};
+use crate::fst::state::RunState;
 use crate::fst::*;
+use crate::utils::deadline::Deadline;
+use crate::utils::logs::{prune_outputs, resolve_output};
+use crate::utils::output::Prefix;
+use crate::utils::reaper::{Event, Reaper};
 use crate::utils::traits::{CommandConfig, WaitSchedule};
-use anyhow::{Context, Result, anyhow, bail, ensure};
+use anyhow::{Context, Result, bail, ensure};
 use chrono::Local;
 use clap::Parser;
 use cron::Schedule;
 use libc::{SIG_IGN, SIGHUP};
 use libc::{fork, signal};
+use std::collections::HashMap;
 use std::fs;
 use std::future::IntoFuture;
+use std::io::{self, IsTerminal};
+use std::os::unix::process::ExitStatusExt;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::Instant;
 use tokio::runtime::Runtime;
 use tokio::task::JoinHandle;
 
@@ -24,6 +37,20 @@ pub struct Run {
   /// Can be many task ids with comma separated values.
   #[arg(long = "start", short = 's', number_of_values = 1)]
   starts: Vec<String>,
+  /// Resume the previous run from its state file, tasks already done will not be executed again.
+  /// The state file is ignored when the configuration changed since the previous run.
+  #[arg(long = "resume", short = 'r', conflicts_with = "starts")]
+  resume: bool,
+  #[command(flatten)]
+  selection: Selection,
+  /// Set the variable used by `{{ key }}` templates, it overrides the `vars` of the configurations
+  /// and the environment variables. Can be used many times to set many variables.
+  #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var, number_of_values = 1)]
+  vars: Vec<(String, String)>,
+  /// Print the tasks grouped in waves that can run in parallel, with their commands, working
+  /// directories and outputs. Nothing is executed.
+  #[arg(long = "dry-run", short = 'n', conflicts_with_all = ["background", "cron"])]
+  dry_run: bool,
   /// Run the task in background
   #[arg(long = "background", short = 'b')]
   background: bool,
@@ -32,6 +59,22 @@ pub struct Run {
   cron: Option<Schedule>,
 }
 
+/// The stdout and stderr of a task run, resolved once for all its steps and attempts.
+type Outputs = (Option<String>, Option<String>);
+
+struct Process {
+  label: String,
+  pid: u32,
+  outputs: Outputs,
+  deadline: Deadline,
+  attempt: u32,
+  printers: Vec<thread::JoinHandle<()>>,
+  steps: Vec<StepExit>,
+  started: Instant,
+  /// Whether the process is the `if` command of the task, the task starts when it succeeds.
+  checking: bool,
+}
+
 impl Run {
   pub fn exec(&self) -> Result<()> {
     for config in &self.config {
@@ -59,8 +102,12 @@ impl Run {
       self.cron.wait(timezone);
 
       for (i, config) in self.config.iter().enumerate() {
-        let starts = if i == 0 { self.starts.clone() } else { vec![] };
-        self.run(&config.as_path(), &starts)?;
+        let (starts, selection) = if i == 0 {
+          (self.starts.clone(), Some(&self.selection))
+        } else {
+          (vec![], None)
+        };
+        self.run(&config.as_path(), &starts, selection)?;
       }
       if self.cron.is_none() {
         return Ok(());
@@ -68,13 +115,24 @@ impl Run {
     }
   }
 
-  fn run(&self, config_path: &Path, starts: &Vec<String>) -> Result<()> {
+  fn run(
+    &self,
+    config_path: &Path,
+    starts: &Vec<String>,
+    selection: Option<&Selection>,
+  ) -> Result<()> {
     let rt = Runtime::new()?;
-    let yaml = fs::read_to_string(config_path)
-      .with_context(|| format!("Can't read the config file {}", config_path.display()))?;
+    let yaml = include::read(config_path)?;
 
     let mut config = Config::from_str(yaml.as_str())
       .with_context(|| format!("Can't process the config file {}", config_path.display()))?;
+    if let Some(selection) = selection {
+      selection.apply(&mut config)?;
+    }
+    let overrides = self.vars.iter().cloned().collect();
+    config
+      .resolve_vars(&overrides)
+      .with_context(|| format!("Can't process the config file {}", config_path.display()))?;
 
     if config.tasks().is_empty() {
       bail!(
@@ -83,39 +141,37 @@ impl Run {
       );
     }
 
-    let mut graph = TaskFst::new();
-    for task in config.tasks_values_mut() {
-      task.set_state(graph.add_state(task.id()));
-    }
-
-    for task in config.tasks().values() {
-      if (task.depends_on().len() == 0 && starts.len() == 0)
-        || (starts.len() > 0 && starts.contains(task.id()))
-      {
-        graph.add_start_state(task.state());
-      } else {
-        for prev in task.depends_on().iter() {
-          let err_msg = anyhow!("{} depends on {} but does not exists", task.id(), prev);
-          let prev_state = config.tasks().get(prev).ok_or(err_msg)?.state();
-          graph.add_arc(prev_state, task.state());
-        }
-      }
-    }
+    // Variables change the commands, a run with other values starts from scratch
+    let mut vars = config.vars(&overrides).into_iter().collect::<Vec<_>>();
+    vars.sort();
+    let hash = RunState::hash(&format!("{}\n{:?}", yaml, vars));
+    let state_file = RunState::path(config_path);
+    let mut state_path = Some(state_file.clone());
+    let graph = TaskFst::from_config(&mut config, starts)?;
 
-    if graph.is_cyclic() {
-      bail!("Can't execute your configuration. There is a deadlock in your tasks !");
+    if let Some(cycle) = graph.find_cycle() {
+      bail!(
+        "Can't execute your configuration. There is a deadlock in your tasks: {}",
+        cycle.join(" -> ")
+      );
     }
 
-    let processes: &mut Vec<Option<std::process::Child>> = &mut vec![];
+    let processes: &mut Vec<Option<Process>> = &mut vec![];
+    let retries: &mut Vec<Option<(Instant, u32, Outputs)>> = &mut vec![];
     for _ in 0..graph.len() {
       processes.push(None);
+      retries.push(None);
     }
 
     let mut joins: Vec<JoinHandle<()>> = vec![];
-    let mut exit_success = 0;
-    let mut exit_failure = 0;
+    let mut printers: Vec<thread::JoinHandle<()>> = vec![];
+    let mut resume = Resume::default();
     let mut ask_for_exit = false;
     let graph_iter = &mut graph.iter();
+    let reaper = Reaper::new();
+    let states: HashMap<&String, usize> = (0..graph.len())
+      .map(|id| (graph.get_state_from_id(id).label(), id))
+      .collect();
 
     if starts.len() != 0 {
       graph
@@ -126,88 +182,554 @@ impl Run {
         .for_each(|(state, _)| graph_iter.set_done(state));
     }
 
+    if self.resume {
+      if let Some(state) = RunState::load(&state_file, &hash)? {
+        for id in 0..graph.len() {
+          if state.status(graph.get_state_from_id(id).label()) == Some(&TaskStatus::Done) {
+            graph_iter.set_done(id);
+          }
+        }
+        graph_iter.refresh_next();
+      } else {
+        eprintln!(
+          "No state to resume for the config file {}, all tasks will be executed",
+          config_path.display()
+        );
+      }
+    }
+
+    if self.dry_run {
+      println!("{}", config_path.display());
+      self.print_plan(&config, graph_iter.waves(config.concurrency()));
+      return Ok(());
+    }
+
+    if let Some(retention) = config.log_retention() {
+      for task in config.tasks().values() {
+        for output in [config.task_stdout(task), config.task_stderr(task)] {
+          prune_outputs(output, task.id(), retention.keep_last())
+            .with_context(|| format!("Can't prune the logs of the task {}", task.id()))?;
+        }
+      }
+    }
+
+    save_state(
+      &mut state_path,
+      RunState::new(hash.clone(), &graph, graph_iter),
+    );
+    // SIGINT and SIGTERM are forwarded to the tasks until the end of the run
+    let signals = reaper.catch_stop_signals()?;
+    let mut interrupts = 0;
+
     loop {
       if graph_iter.has_next()
         && (graph_iter.n_in_progress() < config.concurrency() || config.concurrency() < 0)
         && !ask_for_exit
       {
         let task = graph_iter.next().unwrap();
-        let label = task.label().to_string();
-        let cmd_line = config.tasks().get(&label).unwrap().full_command();
-        let child = Command::new("sh")
-          .arg("-c")
-          .arg(cmd_line.to_string())
-          .stdin(self.stdin())
-          .stdout_opt(config.stdout(), !self.background)?
-          .stderr_opt(config.stderr(), !self.background)?
-          .working_dir(config.working_dir())?
-          .spawn()
-          .with_context(|| format!("Can't run command `{}`", cmd_line))?;
-        processes[task.id()] = Some(child);
+        let task_config = config.tasks().get(task.label()).unwrap();
+        let status = |id: &str| {
+          states
+            .get(&id.to_string())
+            .map(|s| graph_iter.status(*s).clone())
+        };
+        let command = task_config.condition().as_ref().and_then(|c| c.command());
+        if self.should_skip(&config, task_config, status) {
+          if config.task_on_skip(task_config) == &OnSkip::SkipDependents {
+            graph_iter.skip_dependents(task.id());
+          }
+          graph_iter.mark_skipped(task.id());
+        } else if let Some(command) = command {
+          processes[task.id()] =
+            Some(self.check(&config, task_config, command, task.id(), &reaper)?);
+        } else {
+          let outputs = self.outputs(&config, task_config);
+          processes[task.id()] =
+            Some(self.spawn(&config, task_config, 1, outputs, task.id(), &reaper)?);
+        }
+        save_state(
+          &mut state_path,
+          RunState::new(hash.clone(), &graph, graph_iter),
+        );
       } else if graph_iter.is_done() {
         break;
       } else {
-        let mut done = 0;
         for id in 0..processes.len() {
-          if let Some(child) = processes[id].as_mut() {
-            if let Ok(Some(exit)) = child.try_wait() {
-              let label = graph.get_state_from_id(id).label().to_string();
-              let is_failure = if exit.success() {
-                exit_success = exit_success + 1;
-                false
-              } else {
-                exit_failure = exit_failure + 1;
-                true
-              };
-
-              done = done + 1;
-              graph_iter.mark_done(id);
-              processes[id] = None;
-
-              if let Some(notification) = config.notification().clone() {
-                let task = config.tasks().get(&label).unwrap().clone();
-                let join = rt.spawn(async move {
-                  notification.notify_task_end(&task, exit).await;
-                });
-                joins.push(join);
-                joins = joins
-                  .into_iter()
-                  .filter(|j| !j.is_finished())
-                  .collect::<Vec<JoinHandle<()>>>();
-              }
-              let on_failure = config.tasks().get(&label).unwrap().on_failure().as_ref();
+          if let Some((at, _, _)) = retries[id]
+            && at <= Instant::now()
+          {
+            let (_, attempt, outputs) = retries[id].take().unwrap();
+            let task = config
+              .tasks()
+              .get(graph.get_state_from_id(id).label())
+              .unwrap();
+            processes[id] = Some(self.spawn(&config, task, attempt, outputs, id, &reaper)?);
+          }
+        }
+
+        if graph_iter.n_in_progress() == 0 && ask_for_exit {
+          break;
+        }
+
+        // Sleep until a child exits, a retry is due or a timeout is reached
+        let until = retries
+          .iter()
+          .flatten()
+          .map(|(at, _, _)| *at)
+          .chain(
+            processes
+              .iter()
+              .flatten()
+              .filter_map(|p| p.deadline.next_check()),
+          )
+          .min();
+        if until.is_none() && processes.iter().all(|p| p.is_none()) {
+          break;
+        }
+
+        let event = reaper.next(until);
+        if let Some(Event::Signal(signal)) = event {
+          interrupts += 1;
+          if interrupts == 1 {
+            eprintln!("Received signal {}, terminating the running tasks", signal);
+          } else {
+            eprintln!("Received signal {}, killing the running tasks", signal);
+          }
+          ask_for_exit = true;
+          for (id, retry) in retries.iter_mut().enumerate() {
+            if retry.take().is_some() {
+              resume.interrupted += 1;
+              graph_iter.mark_failed(id);
+            }
+          }
+          for process in processes.iter_mut().flatten() {
+            if let Err(e) = process.deadline.interrupt(process.pid) {
+              eprintln!("Can't stop the task {}: {}", process.label, e);
+            }
+          }
+          save_state(
+            &mut state_path,
+            RunState::new(hash.clone(), &graph, graph_iter),
+          );
+        } else if let Some(Event::Exit(id, status)) = event {
+          let mut process = processes[id].take().unwrap();
+          let status =
+            status.with_context(|| format!("Can't wait for the task {}", process.label))?;
+          let task = config.tasks().get(&process.label).unwrap();
+          printers.append(&mut process.printers);
 
-              if is_failure && on_failure.unwrap_or(config.on_failure()) == &OnFailure::Exit {
-                ask_for_exit = true;
+          if process.checking {
+            if process.deadline.interrupted() {
+              resume.interrupted += 1;
+              graph_iter.mark_failed(id);
+            } else if ask_for_exit {
+              // The task did not run, the next resume will check it again
+              graph_iter.mark_failed(id);
+            } else if status.success() && !process.deadline.timed_out() {
+              let outputs = self.outputs(&config, task);
+              processes[id] = Some(self.spawn(&config, task, 1, outputs, id, &reaper)?);
+            } else {
+              if config.task_on_skip(task) == &OnSkip::SkipDependents {
+                graph_iter.skip_dependents(id);
               }
+              graph_iter.mark_skipped(id);
+            }
+            save_state(
+              &mut state_path,
+              RunState::new(hash.clone(), &graph, graph_iter),
+            );
+            continue;
+          }
+
+          if config.task_runs_steps(task) {
+            process
+              .steps
+              .push(StepExit::new(status, process.started.elapsed()));
+            if let Some(step) = task.next_step(&process.steps)
+              && !process.deadline.timed_out()
+              && !process.deadline.interrupted()
+            {
+              let argv = config.task_argv(task, Some(step));
+              self.start(&config, task, &argv, id, &reaper, &mut process)?;
+              processes[id] = Some(process);
+              continue;
             }
           }
+          let exit = TaskExit::new(status, process.deadline.timed_out(), process.attempt)
+            .with_interrupted(process.deadline.interrupted())
+            .with_steps(task, process.steps);
+
+          if let Some(retry) = task.retry()
+            && retry.should_retry(&exit, exit.attempt())
+            && !process.deadline.interrupted()
+          {
+            let at = Instant::now() + retry.delay_after(exit.attempt());
+            retries[id] = Some((at, exit.attempt() + 1, process.outputs));
+            continue;
+          }
+
+          let on_failure = task.on_failure().as_ref().unwrap_or(config.on_failure());
+          let is_failure = if exit.success() {
+            resume.success += 1;
+            graph_iter.mark_done(id);
+            false
+          } else if process.deadline.interrupted() {
+            resume.interrupted += 1;
+            graph_iter.mark_failed(id);
+            false
+          } else {
+            resume.failures += 1;
+            if on_failure == &OnFailure::SkipDependents {
+              graph_iter.skip_dependents(id);
+            }
+            graph_iter.mark_failed(id);
+            true
+          };
+          save_state(
+            &mut state_path,
+            RunState::new(hash.clone(), &graph, graph_iter),
+          );
+
+          if let Some(notification) = config.notification().clone() {
+            let task = task.clone();
+            let join = rt.spawn(async move {
+              notification.notify_task_end(&task, exit).await;
+            });
+            joins.push(join);
+            joins = joins
+              .into_iter()
+              .filter(|j| !j.is_finished())
+              .collect::<Vec<JoinHandle<()>>>();
+          }
+          if is_failure && on_failure == &OnFailure::Exit {
+            ask_for_exit = true;
+          }
         }
 
-        if graph_iter.n_in_progress() == 0 && ask_for_exit {
-          break;
-        } else if done == 0 {
-          std::thread::sleep(std::time::Duration::from_millis(100));
+        for process in processes.iter_mut().flatten() {
+          if let Err(e) = process.deadline.check(process.pid) {
+            eprintln!("Can't stop the timed out task: {}", e);
+          }
         }
       }
     }
 
+    drop(signals);
+
     for join in joins.into_iter() {
       rt.block_on(join.into_future())?;
     }
 
+    for printer in printers.into_iter() {
+      let _ = printer.join();
+    }
+
+    if let Some(path) = state_path
+      && resume.failures == 0
+      && !ask_for_exit
+    {
+      // Nothing to resume, the next run will start from scratch
+      let _ = fs::remove_file(path);
+    }
+
+    resume.skipped = graph_iter.n_skipped();
     if let Some(notification) = config.notification().clone() {
-      rt.block_on(notification.notify_all_tasks_end(exit_success, exit_failure, ask_for_exit));
+      rt.block_on(notification.notify_all_tasks_end(&resume, ask_for_exit));
+    }
+
+    Ok(())
+  }
+
+  /// Whether the results or the environment of the condition of the task are false, its command
+  /// is checked apart by `check`.
+  fn should_skip<F: Fn(&str) -> Option<TaskStatus>>(
+    &self,
+    config: &Config,
+    task: &Task,
+    status: F,
+  ) -> bool {
+    let Some(condition) = task.condition() else {
+      return false;
+    };
+    let var = |name: &str| {
+      task
+        .env()
+        .get(name)
+        .or(config.env().get(name))
+        .cloned()
+        .or_else(|| std::env::var(name).ok())
+    };
+    !condition.matches_results(status) || !condition.matches_env(var)
+  }
+
+  /// Start the condition command of the task with the task settings and its timeout, the reaper
+  /// reports its exit like the one of a task.
+  fn check(
+    &self,
+    config: &Config,
+    task: &Task,
+    command: &String,
+    id: usize,
+    reaper: &Reaper<usize>,
+  ) -> Result<Process> {
+    let mut process = Process {
+      label: task.id().clone(),
+      pid: 0,
+      outputs: (None, None),
+      deadline: Deadline::new(config.task_timeout(task), config.task_grace_period(task)),
+      attempt: 1,
+      printers: vec![],
+      steps: vec![],
+      started: Instant::now(),
+      checking: true,
+    };
+    let argv = config.shell_argv(task, &CommandArgs::Line(command.clone()));
+    let Some((program, args)) = argv.split_first() else {
+      bail!(
+        "The condition of the task {} has no command to run",
+        task.id()
+      );
+    };
+    // The errors of the condition go with the ones of the task
+    let stderr = resolve_output(config.task_stderr(task), task.id(), Local::now());
+    let spawned = Command::new(program)
+      .args(args)
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr_opt(&stderr, !self.background)?
+      .working_dir(config.task_working_dir(task))?
+      .task_env(config, task)?
+      .task_group(false)
+      .spawn();
+    match spawned {
+      Ok(child) => {
+        process.pid = child.id();
+        reaper.watch(id, child);
+      }
+      Err(e) => {
+        // The condition is false, like a command not found by the shell
+        eprintln!(
+          "Can't check the condition `{}` of {}: {}",
+          command,
+          task.id(),
+          e
+        );
+        reaper.exited(id, ExitStatus::from_raw(127 << 8));
+      }
+    }
+    Ok(process)
+  }
+
+  fn spawn(
+    &self,
+    config: &Config,
+    task: &Task,
+    attempt: u32,
+    outputs: Outputs,
+    id: usize,
+    reaper: &Reaper<usize>,
+  ) -> Result<Process> {
+    let mut process = Process {
+      label: task.id().clone(),
+      pid: 0,
+      outputs,
+      deadline: Deadline::new(config.task_timeout(task), config.task_grace_period(task)),
+      attempt,
+      printers: vec![],
+      steps: vec![],
+      started: Instant::now(),
+      checking: false,
+    };
+    // In steps mode, the next steps are started when the previous one exits
+    let argv = config.task_argv(task, config.task_runs_steps(task).then_some(0));
+    self.start(config, task, &argv, id, reaper, &mut process)?;
+    Ok(process)
+  }
+
+  /// Start a program of the task in the process.
+  fn start(
+    &self,
+    config: &Config,
+    task: &Task,
+    argv: &[String],
+    id: usize,
+    reaper: &Reaper<usize>,
+    process: &mut Process,
+  ) -> Result<()> {
+    let (stdout, stderr) = &process.outputs;
+    let Some((program, args)) = argv.split_first() else {
+      bail!("The task {} has no command to run", task.id());
+    };
+    let mut command = Command::new(program);
+    command
+      .args(args)
+      .stdin(self.stdin())
+      .stdout_opt(stdout, !self.background)?
+      .stderr_opt(stderr, !self.background)?
+      .working_dir(config.task_working_dir(task))?
+      .task_env(config, task)?
+      .task_group(false);
+
+    // Outputs inherited from runtasktic are piped to prefix each line with the task id
+    let prefixed = config.output() == &OutputMode::Prefixed && !self.background;
+    if prefixed && stdout.is_none() {
+      command.stdout(Stdio::piped());
+    }
+    if prefixed && stderr.is_none() {
+      command.stderr(Stdio::piped());
+    }
+
+    let mut child = match command.spawn() {
+      Ok(child) => child,
+      Err(e) => {
+        // Like a shell, the task fails with 127 and the run goes on
+        eprintln!(
+          "Can't run command `{}` of {}: {}",
+          argv.join(" "),
+          task.id(),
+          e
+        );
+        process.pid = 0;
+        process.started = Instant::now();
+        reaper.exited(id, ExitStatus::from_raw(127 << 8));
+        return Ok(());
+      }
+    };
+
+    let width = config.tasks().keys().map(|id| id.len()).max().unwrap_or(0);
+    let prefix = Prefix::new(task.id(), width, config.output_timestamp());
+    if let Some(stdout) = child.stdout.take() {
+      process.printers.push(prefix.clone().print(stdout, false));
+    }
+    if let Some(stderr) = child.stderr.take() {
+      process.printers.push(prefix.print(stderr, true));
     }
 
+    process.pid = child.id();
+    process.started = Instant::now();
+    reaper.watch(id, child);
     Ok(())
   }
 
+  /// Resolve the output templates of a task run, with the date and time of its start.
+  fn outputs(&self, config: &Config, task: &Task) -> Outputs {
+    let now = Local::now();
+    (
+      resolve_output(config.task_stdout(task), task.id(), now),
+      resolve_output(config.task_stderr(task), task.id(), now),
+    )
+  }
+
+  fn print_plan(&self, config: &Config, waves: Vec<Vec<TaskFstState>>) {
+    let output = |output: Option<String>, default_file: &str| match output {
+      Some(output) => output,
+      None if self.background => default_file.to_string(),
+      None => "inherit".to_string(),
+    };
+    for (i, wave) in waves.iter().enumerate() {
+      println!("Wave {}:", i + 1);
+      for state in wave {
+        let task = config.tasks().get(state.label()).unwrap();
+        let working_dir = config.task_working_dir(task).clone();
+        let (stdout, stderr) = self.outputs(config, task);
+        println!("  {}", task.id());
+        if config.task_runs_steps(task) {
+          for (i, command) in task.commands().iter().enumerate() {
+            println!("    step {}: {}", i + 1, command.command());
+          }
+        } else {
+          println!("    command: {}", task.full_command());
+        }
+        println!(
+          "    working_dir: {}",
+          working_dir.unwrap_or(".".to_string())
+        );
+        println!("    stdout: {}", output(stdout, "runtasktic.out"));
+        println!("    stderr: {}", output(stderr, "runtasktic.err"));
+      }
+    }
+  }
+
+  /// The tasks run in background process groups, they would be stopped by SIGTTIN reading the
+  /// terminal.
   fn stdin(&self) -> Stdio {
-    if self.background {
+    if self.background || io::stdin().is_terminal() {
       Stdio::null()
     } else {
       Stdio::inherit()
     }
   }
 }
+
+pub(crate) fn parse_var(var: &str) -> Result<(String, String), String> {
+  match var.split_once('=') {
+    Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+    _ => Err(format!("invalid variable `{}`, expected KEY=VALUE", var)),
+  }
+}
+
+fn save_state(path: &mut Option<PathBuf>, state: RunState) {
+  if let Some(p) = path
+    && let Err(e) = state.save(p)
+  {
+    eprintln!("{:?}", e);
+    // Stop saving the state after the first failure
+    *path = None;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn condition_outside_dependencies() {
+    let config = "tests/resources/condition_invalid.yml";
+    let run = Run::try_parse_from(["run", config]).unwrap();
+    let err = run.exec().unwrap_err();
+    assert_eq!(
+      format!("{:#}", err),
+      format!(
+        "Can't process the config file {}: The condition of b checks a which is not in its dependencies",
+        config
+      )
+    );
+    assert!(!RunState::path(Path::new(config)).exists());
+  }
+
+  /// A chain of short tasks, each one starts when the previous one exits, the elapsed time is the
+  /// latency of the scheduler.
+  #[test]
+  #[ignore = "measures the scheduler, run with --ignored --nocapture"]
+  fn many_short_tasks() -> Result<()> {
+    const TASKS: u32 = 300;
+    let dir = std::env::temp_dir().join(format!("runtasktic-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let config = dir.join("chain.yml");
+    let tasks = (0..TASKS)
+      .map(|i| match i {
+        0 => "  t0:\n    commands: [ 'true' ]\n".to_string(),
+        i => format!(
+          "  t{}:\n    commands: [ 'true' ]\n    depends_on: [ t{} ]\n",
+          i,
+          i - 1
+        ),
+      })
+      .collect::<String>();
+    fs::write(&config, format!("tasks:\n{}", tasks))?;
+
+    let run = Run::try_parse_from(["run", config.to_str().unwrap()])?;
+    let started = Instant::now();
+    run.exec()?;
+    let elapsed = started.elapsed();
+    fs::remove_dir_all(&dir)?;
+
+    println!(
+      "{} chained tasks in {:?}, {:?} per task",
+      TASKS,
+      elapsed,
+      elapsed / TASKS
+    );
+    Ok(())
+  }
+}
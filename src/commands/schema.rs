@@ -0,0 +1,13 @@
+use crate::config::Config;
+use anyhow::Result;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+pub struct Schema {}
+
+impl Schema {
+  pub fn exec(&self) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+    Ok(())
+  }
+}
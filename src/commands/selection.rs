@@ -0,0 +1,57 @@
+use crate::config::Config;
+use crate::fst::TaskFst;
+use anyhow::{Result, anyhow};
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct Selection {
+  /// Select only this task and all its dependencies, like make does.
+  /// Can be used many times to select many targets.
+  #[arg(long = "target", number_of_values = 1)]
+  targets: Vec<String>,
+  /// Remove this task and all the tasks depending on it from the selection.
+  /// Can be used many times to exclude many tasks.
+  #[arg(long = "exclude", short = 'x', number_of_values = 1)]
+  excludes: Vec<String>,
+}
+
+impl Selection {
+  /// Remove from the configuration all the tasks outside of the selection.
+  pub fn apply(&self, config: &mut Config) -> Result<()> {
+    if self.targets.is_empty() && self.excludes.is_empty() {
+      return Ok(());
+    }
+
+    let graph = TaskFst::from_config(config, &[])?;
+    let states = |ids: &Vec<String>| -> Result<Vec<usize>> {
+      ids
+        .iter()
+        .map(|id| {
+          config
+            .tasks()
+            .get(id)
+            .map(|task| task.state())
+            .ok_or(anyhow!(
+              "The task `{}` does not exist in your config file",
+              id
+            ))
+        })
+        .collect()
+    };
+
+    let selected = if self.targets.is_empty() {
+      vec![true; graph.len()]
+    } else {
+      graph.ancestors(&states(&self.targets)?)
+    };
+    let excluded = graph.descendants(&states(&self.excludes)?);
+
+    let keep = (0..graph.len())
+      .filter(|s| selected[*s] && !excluded[*s])
+      .map(|s| graph.get_state_from_id(s).label().clone())
+      .collect::<Vec<String>>();
+    config.retain_tasks(|id| keep.contains(id));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,56 @@
+use crate::commands::run::parse_var;
+use crate::config::validate::{Severity, validate};
+use anyhow::{Context, Result, anyhow};
+use clap::{Parser, ValueEnum};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+pub struct Validate {
+  /// Configurations path (YAML)
+  #[arg(required = true)]
+  config: Vec<PathBuf>,
+  /// Output format of the diagnostics.
+  #[arg(long = "format", short = 'f', value_enum, default_value_t = Format::Text)]
+  format: Format,
+  /// Set the variable used by `{{ key }}` templates, like `run --var`. Can be used many times to
+  /// set many variables.
+  #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var, number_of_values = 1)]
+  vars: Vec<(String, String)>,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum Format {
+  Text,
+  Json,
+}
+
+impl Validate {
+  pub fn exec(&self) -> Result<()> {
+    let vars = self.vars.iter().cloned().collect();
+    let mut diagnostics = vec![];
+    for config in &self.config {
+      let yaml = fs::read_to_string(config)
+        .with_context(|| anyhow!("Can't read the config file {}", config.display()))?;
+      let findings = validate(&config.display().to_string(), &yaml, &vars);
+      if self.format == Format::Text {
+        if findings.is_empty() {
+          println!("{}: valid", config.display());
+        }
+        for finding in findings.iter() {
+          println!("{}", finding);
+        }
+      }
+      diagnostics.extend(findings);
+    }
+
+    if self.format == Format::Json {
+      println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+    }
+
+    if diagnostics.iter().any(|d| d.severity() == Severity::Error) {
+      std::process::exit(1);
+    }
+    Ok(())
+  }
+}
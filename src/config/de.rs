@@ -1,6 +1,7 @@
 use serde::de::Deserialize;
 use std::collections::HashMap;
 use std::env::var as get_env;
+use std::time::Duration;
 
 const ENV_NOTIFICATION_SLACK_URL: &str = "RUNTASKTIK_NOTIFICATION_SLACK_URL";
 const ENV_NOTIFICATION_SLACK_CHANNEL: &str = "RUNTASKTIK_NOTIFICATION_SLACK_CHANNEL";
@@ -9,6 +10,28 @@ const ENV_NOTIFICATION_SLACK_USERNAME: &str = "RUNTASKTIK_NOTIFICATION_SLACK_USE
 const ENV_NOTIFICATION_DISCORD_URL: &str = "RUNTASKTIK_NOTIFICATION_DISCORD_URL";
 const ENV_NOTIFICATION_DISCORD_USERNAME: &str = "RUNTASKTIK_NOTIFICATION_DISCORD_USERNAME";
 
+const ENV_NOTIFICATION_TEAMS_URL: &str = "RUNTASKTIK_NOTIFICATION_TEAMS_URL";
+
+const ENV_NOTIFICATION_MATTERMOST_URL: &str = "RUNTASKTIK_NOTIFICATION_MATTERMOST_URL";
+const ENV_NOTIFICATION_MATTERMOST_CHANNEL: &str = "RUNTASKTIK_NOTIFICATION_MATTERMOST_CHANNEL";
+const ENV_NOTIFICATION_MATTERMOST_USERNAME: &str = "RUNTASKTIK_NOTIFICATION_MATTERMOST_USERNAME";
+
+const ENV_NOTIFICATION_GOOGLE_CHAT_URL: &str = "RUNTASKTIK_NOTIFICATION_GOOGLE_CHAT_URL";
+
+const ENV_NOTIFICATION_WEBHOOK_URL: &str = "RUNTASKTIK_NOTIFICATION_WEBHOOK_URL";
+
+const ENV_NOTIFICATION_TELEGRAM_BOT_TOKEN: &str = "RUNTASKTIK_NOTIFICATION_TELEGRAM_BOT_TOKEN";
+const ENV_NOTIFICATION_TELEGRAM_CHAT_ID: &str = "RUNTASKTIK_NOTIFICATION_TELEGRAM_CHAT_ID";
+
+const ENV_NOTIFICATION_MATRIX_ROOM_ID: &str = "RUNTASKTIK_NOTIFICATION_MATRIX_ROOM_ID";
+const ENV_NOTIFICATION_MATRIX_ACCESS_TOKEN: &str = "RUNTASKTIK_NOTIFICATION_MATRIX_ACCESS_TOKEN";
+
+const ENV_NOTIFICATION_NTFY_URL: &str = "RUNTASKTIK_NOTIFICATION_NTFY_URL";
+const ENV_NOTIFICATION_NTFY_TOKEN: &str = "RUNTASKTIK_NOTIFICATION_NTFY_TOKEN";
+
+const ENV_NOTIFICATION_GOTIFY_URL: &str = "RUNTASKTIK_NOTIFICATION_GOTIFY_URL";
+const ENV_NOTIFICATION_GOTIFY_TOKEN: &str = "RUNTASKTIK_NOTIFICATION_GOTIFY_TOKEN";
+
 const ENV_NOTIFICATION_EMAIL_SMTP_HOSTNAME: &str = "RUNTASKTIK_NOTIFICATION_EMAIL_SMTP_HOSTNAME";
 const ENV_NOTIFICATION_EMAIL_SMTP_USERNAME: &str = "RUNTASKTIK_NOTIFICATION_EMAIL_SMTP_USERNAME";
 const ENV_NOTIFICATION_EMAIL_SMTP_SECRET: &str = "RUNTASKTIK_NOTIFICATION_EMAIL_SMTP_SECRET";
@@ -17,6 +40,18 @@ pub fn default_concurrency() -> i64 {
   -1
 }
 
+pub fn default_grace_period() -> Duration {
+  Duration::from_secs(10)
+}
+
+pub fn default_hook_timeout() -> Duration {
+  Duration::from_secs(30)
+}
+
+pub fn default_retry_attempts() -> u32 {
+  2
+}
+
 pub fn default_email_port() -> u16 {
   587
 }
@@ -29,19 +64,83 @@ pub fn default_email_subject() -> String {
   format!("Runtasktik: task ended")
 }
 
+pub fn default_interrupted_message() -> String {
+  String::from(
+    "Tasks interrupted. Got {resume.success} success, {resume.failures} failure and {resume.interrupted} interrupted.",
+  )
+}
+
 pub fn deserialize_task<'de, D>(deserializer: D) -> Result<HashMap<String, super::Task>, D::Error>
 where
   D: serde::Deserializer<'de>,
 {
-  if let Ok(mut tasks) = HashMap::deserialize(deserializer) {
-    for e in tasks.iter_mut() {
-      let (id, task): (&String, &mut super::Task) = e;
-      task.id = id.clone();
-    }
-    Ok(tasks)
-  } else {
-    Ok(HashMap::new())
+  let mut tasks: HashMap<String, super::Task> =
+    Option::deserialize(deserializer)?.unwrap_or_default();
+  for (id, task) in tasks.iter_mut() {
+    task.id = id.clone();
   }
+  Ok(tasks)
+}
+
+/// A number of seconds or a duration such as `500ms`, `10s`, `5m` or `1h30m`.
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum RawDuration {
+  Seconds(u64),
+  Text(String),
+}
+
+pub fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  match Option::<RawDuration>::deserialize(deserializer)? {
+    Some(RawDuration::Seconds(secs)) => Ok(Some(Duration::from_secs(secs))),
+    Some(RawDuration::Text(text)) => parse_duration(&text)
+      .map(Some)
+      .map_err(serde::de::Error::custom),
+    None => Ok(None),
+  }
+}
+
+/// Parse durations such as `30`, `500ms`, `10s`, `5m` or `1h30m`. A number without unit is in seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+  let text = text.trim();
+  if text.is_empty() {
+    return Err("Invalid empty duration".to_string());
+  }
+  if let Ok(secs) = text.parse::<u64>() {
+    return Ok(Duration::from_secs(secs));
+  }
+
+  let mut duration = Duration::ZERO;
+  let mut rest = text;
+  while !rest.is_empty() {
+    let digits = rest
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(rest.len());
+    let unit = rest[digits..]
+      .find(|c: char| c.is_ascii_digit())
+      .map(|i| i + digits)
+      .unwrap_or(rest.len());
+    let value = rest[..digits]
+      .parse::<u64>()
+      .map_err(|_| format!("Invalid duration `{}`", text))?;
+    let part = match rest[digits..unit].trim() {
+      "ms" => Some(Duration::from_millis(value)),
+      "s" => Some(Duration::from_secs(value)),
+      "m" => value.checked_mul(60).map(Duration::from_secs),
+      "h" => value.checked_mul(3600).map(Duration::from_secs),
+      "d" => value.checked_mul(86400).map(Duration::from_secs),
+      _ => return Err(format!("Invalid duration `{}`", text)),
+    };
+    duration = part
+      .and_then(|part| duration.checked_add(part))
+      .ok_or_else(|| format!("The duration `{}` is too large", text))?;
+    rest = rest[unit..].trim_start();
+  }
+
+  Ok(duration)
 }
 
 pub fn notification_slack_url<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -99,6 +198,172 @@ where
   }
 }
 
+pub fn notification_teams_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_TEAMS_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_mattermost_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_MATTERMOST_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_mattermost_channel<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_MATTERMOST_CHANNEL) {
+    Ok(Some(env))
+  } else {
+    Option::deserialize(deserializer)
+  }
+}
+
+pub fn notification_mattermost_username<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_MATTERMOST_USERNAME) {
+    Ok(Some(env))
+  } else {
+    Option::deserialize(deserializer)
+  }
+}
+
+pub fn notification_google_chat_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_GOOGLE_CHAT_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn default_telegram_api_url() -> String {
+  "https://api.telegram.org".to_string()
+}
+
+pub fn notification_telegram_bot_token<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_TELEGRAM_BOT_TOKEN) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_telegram_chat_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_TELEGRAM_CHAT_ID) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_matrix_room_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_MATRIX_ROOM_ID) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_matrix_access_token<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_MATRIX_ACCESS_TOKEN) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_ntfy_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_NTFY_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_ntfy_token<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_NTFY_TOKEN) {
+    Ok(Some(env))
+  } else {
+    Option::deserialize(deserializer)
+  }
+}
+
+pub fn notification_gotify_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_GOTIFY_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_gotify_token<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_GOTIFY_TOKEN) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn notification_webhook_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  if let Ok(env) = get_env(ENV_NOTIFICATION_WEBHOOK_URL) {
+    Ok(env)
+  } else {
+    String::deserialize(deserializer)
+  }
+}
+
+pub fn default_webhook_method() -> String {
+  "POST".to_string()
+}
+
+pub fn default_webhook_body() -> serde_json::Value {
+  serde_json::json!({ "message": "{message}" })
+}
+
 pub fn notification_email_smtp_hostname<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
   D: serde::Deserializer<'de>,
@@ -0,0 +1,314 @@
+use crate::config::Include;
+use anyhow::{Context, Result, bail};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INCLUDE_KEY: &str = "include";
+const TASKS_KEY: &str = "tasks";
+const DEPENDS_ON_KEY: &str = "depends_on";
+const CONDITION_KEYS: [&str; 2] = ["if", "when"];
+const CONDITION_TASKS_KEYS: [&str; 2] = ["succeeded", "failed"];
+const ENV_FILE_KEY: &str = "env_file";
+const PATH_KEYS: [&str; 4] = ["working_dir", "stdout", "stderr", ENV_FILE_KEY];
+const TASK_PATH_KEYS: [&str; 3] = ["working_dir", "stdout", "stderr"];
+
+/// The file defining a task and the id of the task in this file.
+pub type Origin = (PathBuf, String);
+
+/// Read a configuration file and merge the files it includes, the result is the YAML of the whole
+/// configuration. A file without includes is returned as is.
+///
+/// Include paths and the env file are relative to the file defining them, the working directories
+/// and the outputs of an included file too. The tasks of an included file with a
+/// namespace are prefixed by `<namespace>.`, their dependencies too. Other settings are merged
+/// recursively: a file takes precedence over its includes and the first includes take precedence
+/// over the next ones.
+pub fn read(path: &Path) -> Result<String> {
+  let yaml = fs::read_to_string(path)
+    .with_context(|| format!("Can't read the config file {}", path.display()))?;
+  let value: Value = serde_yaml::from_str(&yaml)
+    .with_context(|| format!("Can't process the config file {}", path.display()))?;
+  if value.get(INCLUDE_KEY).is_none() && !is_relative(value.get(ENV_FILE_KEY)) {
+    return Ok(yaml);
+  }
+
+  let merged = load(path, &mut vec![], &mut HashMap::new())?;
+  Ok(serde_yaml::to_string(&merged)?)
+}
+
+/// The origin of each task of the configuration, with its id after the merge.
+pub fn origins(path: &Path) -> Result<HashMap<String, Origin>> {
+  let mut origins = HashMap::new();
+  load(path, &mut vec![], &mut origins)?;
+  Ok(origins)
+}
+
+fn load(
+  path: &Path,
+  stack: &mut Vec<PathBuf>,
+  origins: &mut HashMap<String, Origin>,
+) -> Result<Mapping> {
+  let canonical = path
+    .canonicalize()
+    .with_context(|| format!("Can't read the config file {}", path.display()))?;
+  if let Some(i) = stack.iter().position(|p| p == &canonical) {
+    let files = stack[i..]
+      .iter()
+      .chain([&canonical])
+      .map(|p| p.display().to_string())
+      .collect::<Vec<_>>();
+    bail!("There is an include loop: {}", files.join(" -> "));
+  }
+
+  let yaml = fs::read_to_string(path)
+    .with_context(|| format!("Can't read the config file {}", path.display()))?;
+  let mut config = match serde_yaml::from_str(&yaml)
+    .with_context(|| format!("Can't process the config file {}", path.display()))?
+  {
+    Value::Mapping(mapping) => mapping,
+    Value::Null => Mapping::new(),
+    _ => bail!("The config file {} is not a mapping", path.display()),
+  };
+
+  let includes: Vec<Include> = match config.remove(INCLUDE_KEY) {
+    Some(includes) => serde_yaml::from_value(includes)
+      .with_context(|| format!("Can't process the includes of {}", path.display()))?,
+    None => vec![],
+  };
+
+  let dir = path.parent().unwrap_or(Path::new(""));
+  if stack.is_empty() {
+    // The other paths of the main file are relative to the current directory
+    rebase(&mut config, dir, &[ENV_FILE_KEY]);
+  } else {
+    rebase(&mut config, dir, &PATH_KEYS);
+    if let Some(Value::Mapping(tasks)) = config.get_mut(TASKS_KEY) {
+      for task in tasks.values_mut() {
+        if let Value::Mapping(task) = task {
+          rebase(task, dir, &TASK_PATH_KEYS);
+        }
+      }
+    }
+  }
+  if let Some(Value::Mapping(tasks)) = config.get(TASKS_KEY) {
+    for id in tasks.keys().filter_map(|id| id.as_str()) {
+      origins.insert(id.to_string(), (path.to_path_buf(), id.to_string()));
+    }
+  }
+
+  stack.push(canonical);
+  for include in includes {
+    let mut included_origins = HashMap::new();
+    let mut included = load(&dir.join(include.path()), stack, &mut included_origins)?;
+    if let Some(namespace) = include.namespace() {
+      prefix_tasks(&mut included, namespace);
+      included_origins = included_origins
+        .into_iter()
+        .map(|(id, origin)| (format!("{}.{}", namespace, id), origin))
+        .collect();
+    }
+    origins.extend(included_origins);
+    merge_tasks(&mut config, &mut included)
+      .with_context(|| format!("Can't include {} in {}", include.path(), path.display()))?;
+    merge(&mut config, included);
+  }
+  stack.pop();
+
+  Ok(config)
+}
+
+/// Prefix the relative paths of `keys` with `dir`, the directory of the configuration file.
+fn rebase(config: &mut Mapping, dir: &Path, keys: &[&str]) {
+  for key in keys {
+    if let Some(value) = config.get_mut(*key)
+      && is_relative(Some(value))
+      && let Value::String(path) = value
+    {
+      *path = dir.join(&path).display().to_string();
+    }
+  }
+}
+
+/// Whether the value is a relative path. Templates are resolved later, they are left as is.
+fn is_relative(value: Option<&Value>) -> bool {
+  match value {
+    Some(Value::String(path)) => {
+      !path.is_empty() && path != "none" && !path.starts_with('{') && Path::new(path).is_relative()
+    }
+    _ => false,
+  }
+}
+
+/// Rename the tasks of a configuration, their dependencies and the tasks checked by their
+/// conditions with the namespace.
+fn prefix_tasks(config: &mut Mapping, namespace: &str) {
+  let Some(Value::Mapping(tasks)) = config.get_mut(TASKS_KEY) else {
+    return;
+  };
+  let prefix = |id: &Value| Value::String(format!("{}.{}", namespace, id.as_str().unwrap_or("")));
+  *tasks = std::mem::take(tasks)
+    .into_iter()
+    .map(|(id, mut task)| {
+      if let Some(Value::Sequence(depends_on)) = task.get_mut(DEPENDS_ON_KEY) {
+        depends_on.iter_mut().for_each(|prev| *prev = prefix(prev));
+      }
+      for key in CONDITION_KEYS {
+        let Some(condition) = task.get_mut(key) else {
+          continue;
+        };
+        for key in CONDITION_TASKS_KEYS {
+          if let Some(Value::Sequence(tasks)) = condition.get_mut(key) {
+            tasks.iter_mut().for_each(|id| *id = prefix(id));
+          }
+        }
+      }
+      (prefix(&id), task)
+    })
+    .collect();
+}
+
+/// Move the tasks of the included configuration, a task id can be defined only once.
+fn merge_tasks(config: &mut Mapping, included: &mut Mapping) -> Result<()> {
+  let Some(Value::Mapping(tasks)) = included.remove(TASKS_KEY) else {
+    return Ok(());
+  };
+  let entry = config
+    .entry(Value::String(TASKS_KEY.to_string()))
+    .or_insert_with(|| Value::Mapping(Mapping::new()));
+  if entry.is_null() {
+    *entry = Value::Mapping(Mapping::new());
+  }
+  let Value::Mapping(config_tasks) = entry else {
+    bail!("The tasks must be a mapping");
+  };
+  for (id, task) in tasks {
+    if config_tasks.contains_key(&id) {
+      bail!(
+        "The task {} is defined many times",
+        id.as_str().unwrap_or("")
+      );
+    }
+    config_tasks.insert(id, task);
+  }
+  Ok(())
+}
+
+/// Add the settings of `other` missing in `config`, mappings are merged recursively.
+fn merge(config: &mut Mapping, other: Mapping) {
+  for (key, value) in other {
+    match (config.get_mut(&key), value) {
+      (Some(Value::Mapping(current)), Value::Mapping(value)) => merge(current, value),
+      (Some(_), _) => {}
+      (None, value) => {
+        config.insert(key, value);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::config::Config;
+
+  const INCLUDE_YAML: &str = "tests/resources/include.yml";
+
+  #[test]
+  fn read_include_yaml() -> Result<()> {
+    let config = Config::from_str(&read(Path::new(INCLUDE_YAML))?)?;
+    let mut ids = config.tasks().keys().cloned().collect::<Vec<_>>();
+    ids.sort();
+
+    assert_eq!(
+      ids,
+      vec![
+        "deploy",
+        "shared.alert",
+        "shared.build",
+        "shared.lint",
+        "test"
+      ]
+    );
+    assert_eq!(
+      config.tasks().get("shared.build").unwrap().depends_on(),
+      &vec!["shared.lint".to_string()]
+    );
+    let alert = config.tasks().get("shared.alert").unwrap();
+    assert_eq!(
+      alert.condition().as_ref().unwrap().tasks(),
+      vec![&"shared.build".to_string()]
+    );
+    assert_eq!(
+      config.tasks().get("deploy").unwrap().depends_on(),
+      &vec!["shared.build".to_string(), "test".to_string()]
+    );
+    assert_eq!(config.concurrency(), 2);
+    assert_eq!(config.working_dir(), &Some("/tmp".to_string()));
+    let test = config.tasks().get("test").unwrap();
+    assert_eq!(
+      config.task_stdout(test),
+      &Some("tests/resources/include/logs/{task.id}.out".to_string())
+    );
+    assert_eq!(config.task_stderr(test), &Some("none".to_string()));
+    let notification = config.notification().as_ref().unwrap();
+    let names = notification
+      .notifiers()
+      .iter()
+      .map(|notifier| notifier.name())
+      .collect::<Vec<_>>();
+    assert_eq!(names, vec!["print"]);
+    assert_eq!(notification.when(), &crate::config::WhenNotify::Always);
+    Ok(())
+  }
+
+  #[test]
+  fn read_env_file() -> Result<()> {
+    let config = Config::from_str(&read(Path::new("tests/resources/env.yml"))?)?;
+    assert_eq!(
+      config.env_file(),
+      &Some("tests/resources/env.env".to_string())
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn read_without_include() -> Result<()> {
+    let path = "tests/resources/sample.yml";
+    assert_eq!(read(Path::new(path))?, fs::read_to_string(path)?);
+    Ok(())
+  }
+
+  #[test]
+  fn origins() -> Result<()> {
+    let origins = super::origins(Path::new(INCLUDE_YAML))?;
+    assert_eq!(
+      origins.get("shared.build"),
+      Some(&(
+        PathBuf::from("tests/resources/include/shared.yml"),
+        "build".to_string()
+      ))
+    );
+    assert_eq!(
+      origins.get("deploy"),
+      Some(&(PathBuf::from(INCLUDE_YAML), "deploy".to_string()))
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn read_include_loop() {
+    let err = read(Path::new("tests/resources/include/loop.yml")).unwrap_err();
+    assert!(format!("{:?}", err).contains("There is an include loop"));
+  }
+
+  #[test]
+  fn merge() {
+    let mut config: Mapping = serde_yaml::from_str("a: 1\nb:\n  c: 2\n").unwrap();
+    let other: Mapping = serde_yaml::from_str("a: 3\nb:\n  c: 4\n  d: 5\ne: 6\n").unwrap();
+    super::merge(&mut config, other);
+    let expected: Mapping = serde_yaml::from_str("a: 1\nb:\n  c: 2\n  d: 5\ne: 6\n").unwrap();
+    assert_eq!(config, expected);
+  }
+}
@@ -1,13 +1,23 @@
-pub use crate::config::task::Task;
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::config::task::CommandsMode;
+pub use crate::config::task::{CommandArgs, StepExit, Task, TaskExit, parse_matrix_id};
+use crate::notification::{Event, Notifier};
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 mod de;
+pub mod include;
 mod task;
+pub mod validate;
+mod vars;
 
-#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Config {
+  #[serde(default)]
+  include: Vec<Include>,
+  #[serde(default)]
+  vars: HashMap<String, String>,
   #[serde(default, deserialize_with = "self::de::deserialize_task")]
   tasks: HashMap<String, Task>,
   #[serde(default = "self::de::default_concurrency")]
@@ -16,25 +26,82 @@ pub struct Config {
   working_dir: Option<String>,
   stdout: Option<String>,
   stderr: Option<String>,
+  log_retention: Option<LogRetention>,
+  #[serde(default)]
+  output: OutputMode,
+  #[serde(default)]
+  output_timestamp: bool,
+  #[serde(default)]
+  env: HashMap<String, String>,
+  env_file: Option<String>,
+  shell: Option<CommandArgs>,
   #[serde(default)]
   on_failure: OnFailure,
+  #[serde(default)]
+  on_skip: OnSkip,
+  #[serde(default, deserialize_with = "self::de::deserialize_duration")]
+  #[schemars(with = "Option<self::de::RawDuration>")]
+  timeout: Option<Duration>,
+  #[serde(default, deserialize_with = "self::de::deserialize_duration")]
+  #[schemars(with = "Option<self::de::RawDuration>")]
+  grace_period: Option<Duration>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct Notification {
-  slack: Option<Slack>,
-  discord: Option<Discord>,
-  print: Option<Print>,
+/// A file to merge in the configuration, its tasks are prefixed by `<namespace>.` when set.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case", untagged)]
+pub enum Include {
+  Path(String),
+  Namespaced {
+    path: String,
+    namespace: Option<String>,
+  },
+}
+
+/// Declare the notification with its channels, each channel is an optional key of the
+/// configuration and a notifier, in the order of the declaration.
+macro_rules! notification {
+  ($($(#[$meta:meta])* $channel:ident: $type:ty,)*) => {
+    #[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub struct Notification {
+      $($(#[$meta])* $channel: Option<$type>,)*
+      #[serde(default)]
+      when: WhenNotify,
+      #[serde(default)]
+      messages: Messages,
+    }
+
+    impl Notification {
+      /// The channels configured to send the notifications.
+      pub fn notifiers(&self) -> Vec<&dyn Notifier> {
+        let notifiers: Vec<Option<&dyn Notifier>> =
+          vec![$(self.$channel.as_ref().map(|n| n as &dyn Notifier)),*];
+        notifiers.into_iter().flatten().collect()
+      }
+    }
+  };
+}
+
+notification! {
+  print: Print,
+  #[serde(rename = "command")]
+  hook: Hook,
+  slack: Slack,
+  discord: Discord,
   #[serde(rename = "email")]
-  mail: Option<Mail>,
-  #[serde(default)]
-  when: WhenNotify,
-  #[serde(default)]
-  messages: Messages,
+  mail: Mail,
+  teams: Teams,
+  mattermost: Mattermost,
+  google_chat: GoogleChat,
+  telegram: Telegram,
+  matrix: Matrix,
+  ntfy: Ntfy,
+  gotify: Gotify,
+  webhook: Webhook,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Slack {
   #[serde(deserialize_with = "self::de::notification_slack_url")]
@@ -47,7 +114,7 @@ pub struct Slack {
   when: Option<WhenNotify>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Discord {
   #[serde(deserialize_with = "self::de::notification_discord_url")]
@@ -57,14 +124,149 @@ pub struct Discord {
   when: Option<WhenNotify>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Print {
   output: String,
   when: Option<WhenNotify>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+/// A local command executed on each event, the message is written on its stdin.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Hook {
+  command: CommandArgs,
+  #[serde(default, deserialize_with = "self::de::deserialize_duration")]
+  #[schemars(with = "Option<self::de::RawDuration>")]
+  timeout: Option<Duration>,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Teams {
+  #[serde(deserialize_with = "self::de::notification_teams_url")]
+  url: String,
+  title: Option<String>,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Mattermost {
+  #[serde(deserialize_with = "self::de::notification_mattermost_url")]
+  url: String,
+  #[serde(
+    default,
+    deserialize_with = "self::de::notification_mattermost_channel"
+  )]
+  channel: Option<String>,
+  #[serde(
+    default,
+    deserialize_with = "self::de::notification_mattermost_username"
+  )]
+  username: Option<String>,
+  icon_url: Option<String>,
+  icon_emoji: Option<String>,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GoogleChat {
+  #[serde(deserialize_with = "self::de::notification_google_chat_url")]
+  url: String,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Telegram {
+  #[serde(default = "self::de::default_telegram_api_url")]
+  api_url: String,
+  #[serde(deserialize_with = "self::de::notification_telegram_bot_token")]
+  bot_token: String,
+  #[serde(deserialize_with = "self::de::notification_telegram_chat_id")]
+  chat_id: String,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Matrix {
+  homeserver: String,
+  #[serde(deserialize_with = "self::de::notification_matrix_room_id")]
+  room_id: String,
+  #[serde(deserialize_with = "self::de::notification_matrix_access_token")]
+  access_token: String,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Ntfy {
+  #[serde(deserialize_with = "self::de::notification_ntfy_url")]
+  url: String,
+  #[serde(default, deserialize_with = "self::de::notification_ntfy_token")]
+  token: Option<String>,
+  title: Option<String>,
+  priority: Option<u8>,
+  failure_priority: Option<u8>,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Gotify {
+  #[serde(deserialize_with = "self::de::notification_gotify_url")]
+  url: String,
+  #[serde(deserialize_with = "self::de::notification_gotify_token")]
+  token: String,
+  title: Option<String>,
+  priority: Option<u8>,
+  failure_priority: Option<u8>,
+  when: Option<WhenNotify>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Webhook {
+  #[serde(deserialize_with = "self::de::notification_webhook_url")]
+  url: String,
+  #[serde(default = "self::de::default_webhook_method")]
+  method: String,
+  #[serde(default)]
+  headers: BTreeMap<String, String>,
+  auth: Option<WebhookAuth>,
+  #[serde(default = "self::de::default_webhook_body")]
+  body: serde_json::Value,
+  #[serde(default)]
+  format: WebhookFormat,
+  when: Option<WhenNotify>,
+}
+
+/// The credentials of a webhook, the secrets are read from environment variables when sending.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case", untagged)]
+pub enum WebhookAuth {
+  Bearer {
+    token_env: String,
+  },
+  Basic {
+    username: String,
+    password_env: String,
+  },
+}
+
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+  #[default]
+  Json,
+  Form,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Mail {
   from: MailAddress,
@@ -75,7 +277,7 @@ pub struct Mail {
   when: Option<WhenNotify>,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case", untagged)]
 pub enum MailAddress {
   A(String),
@@ -86,7 +288,7 @@ pub enum MailAddress {
   V(Vec<MailAddress>),
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MailSMTP {
   #[serde(deserialize_with = "self::de::notification_email_smtp_hostname")]
@@ -104,7 +306,7 @@ pub struct MailSMTP {
   tls: bool,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum WhenNotify {
   Always,
@@ -113,24 +315,108 @@ pub enum WhenNotify {
   Never,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum OnFailure {
   Continue,
   Exit,
+  SkipDependents,
+}
+
+/// What happens to the dependents of a task skipped because its condition is false.
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnSkip {
+  /// The dependents are executed as if the task succeeded.
+  Continue,
+  /// The dependents are skipped too.
+  #[default]
+  SkipDependents,
 }
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LogRetention {
+  keep_last: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+  #[default]
+  Inherit,
+  Prefixed,
+}
+
+/// Summary of a run sent in the end of run notification.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Resume {
+  pub success: i32,
+  pub failures: i32,
+  pub skipped: i32,
+  pub interrupted: i32,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Retry {
+  #[serde(default = "self::de::default_retry_attempts")]
+  attempts: u32,
+  #[serde(default, deserialize_with = "self::de::deserialize_duration")]
+  #[schemars(with = "Option<self::de::RawDuration>")]
+  delay: Option<Duration>,
+  #[serde(default)]
+  backoff: Backoff,
+  #[serde(default)]
+  on_exit_codes: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Backoff {
+  #[default]
+  Fixed,
+  Exponential,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Messages {
   task_end: String,
   all_tasks_end: String,
   task_failed: String,
+  #[serde(default = "self::de::default_interrupted_message")]
+  interrupted: String,
 }
 
 impl Config {
   pub fn from_str(s: &str) -> Result<Config> {
-    Ok(serde_yaml::from_str(s)?)
+    let mut config: Config = serde_yaml::from_str(s)?;
+    config.expand_matrices()?;
+    if let Some((id, i)) = config.lines_without_shell().first() {
+      bail!(
+        "The command {} of the task {} must be a list of arguments, the task runs without shell",
+        i + 1,
+        id
+      );
+    }
+    if let Some((id, prev)) = config.conditions_outside_dependencies().first() {
+      bail!(
+        "The condition of {} checks {} which is not in its dependencies",
+        id,
+        prev
+      );
+    }
+    Ok(config)
+  }
+
+  /// JSON Schema of the configuration, generated from the structures used to read it.
+  pub fn json_schema() -> schemars::Schema {
+    schemars::schema_for!(Config)
+  }
+
+  pub fn include(&self) -> &Vec<Include> {
+    &self.include
   }
 
   pub fn tasks(&self) -> &HashMap<String, Task> {
@@ -141,6 +427,10 @@ impl Config {
     self.tasks.values_mut()
   }
 
+  pub fn retain_tasks<F: FnMut(&String) -> bool>(&mut self, mut f: F) {
+    self.tasks.retain(|id, _| f(id))
+  }
+
   pub fn concurrency(&self) -> i64 {
     self.concurrency
   }
@@ -161,112 +451,348 @@ impl Config {
     &self.stderr
   }
 
-  pub fn on_failure(&self) -> &OnFailure {
-    &self.on_failure
+  pub fn log_retention(&self) -> &Option<LogRetention> {
+    &self.log_retention
   }
-}
 
-impl Notification {
-  pub fn slack(&self) -> &Option<Slack> {
-    &self.slack
+  pub fn output(&self) -> &OutputMode {
+    &self.output
   }
 
-  pub fn discord(&self) -> &Option<Discord> {
-    &self.discord
+  pub fn output_timestamp(&self) -> bool {
+    self.output_timestamp
   }
 
-  pub fn print(&self) -> &Option<Print> {
-    &self.print
+  pub fn env(&self) -> &HashMap<String, String> {
+    &self.env
   }
 
-  pub fn email(&self) -> &Option<Mail> {
-    &self.mail
+  pub fn env_file(&self) -> &Option<String> {
+    &self.env_file
   }
 
-  pub fn when(&self) -> &WhenNotify {
-    &self.when
+  pub fn on_failure(&self) -> &OnFailure {
+    &self.on_failure
   }
 
-  pub fn messages(&self) -> &Messages {
-    &self.messages
+  pub fn task_on_skip<'a>(&'a self, task: &'a Task) -> &'a OnSkip {
+    task.on_skip().as_ref().unwrap_or(&self.on_skip)
   }
 
-  pub async fn notify_task_end(&self, task: &Task, status_code: std::process::ExitStatus) {
-    if !self.when().should_notify(&WhenNotify::TaskEnd) {
-      return;
+  /// Replace the tasks with a `matrix` by their instances. A dependency on a matrix is replaced by
+  /// all its instances, or only the ones with the values of `id[key=value,...]`.
+  fn expand_matrices(&mut self) -> Result<()> {
+    let mut matrices: HashMap<String, Vec<Task>> = HashMap::new();
+    for task in self.tasks.values().filter(|task| !task.matrix().is_empty()) {
+      matrices.insert(task.id().clone(), task.expand_matrix()?);
+    }
+    if matrices.is_empty() {
+      return Ok(());
     }
 
-    let short_cmd = task.short_command();
-    let id = if task.id().len() > 0 {
-      task.id()
-    } else {
-      &short_cmd
+    let instances = |dependency: &String| -> Vec<String> {
+      let (id, values) = match parse_matrix_id(dependency) {
+        Some((id, values)) if matrices.contains_key(id) => (id, values),
+        _ if matrices.contains_key(dependency) => (dependency.as_str(), Default::default()),
+        _ => return vec![dependency.clone()],
+      };
+      let ids = matrices[id]
+        .iter()
+        .filter(|task| {
+          values
+            .iter()
+            .all(|(key, value)| task.matrix_values().get(key) == Some(value))
+        })
+        .map(|task| task.id().clone())
+        .collect::<Vec<_>>();
+      // Unknown values are reported as a missing dependency
+      if ids.is_empty() {
+        vec![dependency.clone()]
+      } else {
+        ids
+      }
     };
-    let msg = crate::notification::replace_templates(self.messages().task_end());
-    let msg = msg.replace("{task.id}", id);
-    let msg = msg.replace("{task.full_cmd}", &task.full_command());
-    let msg = msg.replace("{task.short_cmd}", &short_cmd);
-    let msg = msg.replace("{task.status_code}", &format!("{}", status_code));
-
-    if let Some(print) = self.print().notify(&WhenNotify::TaskEnd) {
-      if let Err(e) = crate::notification::notification_print(&print, msg.as_str()) {
-        eprintln!("Can't use print notification: {}", e);
+
+    let mut tasks = HashMap::new();
+    for (id, task) in self.tasks.iter() {
+      for mut task in matrices
+        .get(id)
+        .cloned()
+        .unwrap_or_else(|| vec![task.clone()])
+      {
+        task.map_depends_on(&instances);
+        tasks.insert(task.id().clone(), task);
       }
     }
+    self.tasks = tasks;
+    Ok(())
+  }
+
+  /// The variables of the configuration, overridden by the environment variables
+  /// `RUNTASKTIK_VAR_<name>` and then by `overrides`.
+  pub fn vars(&self, overrides: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut vars = self.vars.clone();
+    vars.extend(self::vars::env_vars());
+    vars.extend(overrides.clone());
+    vars
+  }
 
-    if let Some(slack) = self.slack().notify(&WhenNotify::TaskEnd) {
-      if let Err(e) = crate::notification::post_slack(&slack, msg.as_str()) {
-        eprintln!("Can't use slack notification: {}", e);
+  /// Replace the `{{ var }}` templates of the commands, working directories and outputs. Returns
+  /// the unresolved variables with the path of the field using them.
+  pub fn interpolate_vars(
+    &mut self,
+    overrides: &HashMap<String, String>,
+  ) -> Vec<(Vec<String>, String)> {
+    let vars = self.vars(overrides);
+    let mut fields = vec![];
+    for (key, field) in [
+      ("working_dir", &mut self.working_dir),
+      ("stdout", &mut self.stdout),
+      ("stderr", &mut self.stderr),
+    ] {
+      if let Some(value) = field {
+        fields.push((vec![key.to_string()], value));
       }
     }
+    for task in self.tasks.values_mut() {
+      fields.extend(task.templates_mut());
+    }
 
-    if let Some(discord) = self.discord().notify(&WhenNotify::TaskEnd) {
-      if let Err(e) = crate::notification::post_discord(&discord, msg.as_str()) {
-        eprintln!("Can't use discord notification: {}", e);
-      }
+    let mut unresolved = vec![];
+    for (path, field) in fields {
+      let mut names = vec![];
+      *field = self::vars::interpolate(field, &vars, &mut names);
+      unresolved.extend(names.into_iter().map(|name| (path.clone(), name)));
     }
+    unresolved.sort();
+    unresolved
+  }
 
-    if let Some(email) = self.email().notify(&WhenNotify::TaskEnd) {
-      if let Err(e) = crate::notification::notification_email(&email, msg.as_str()).await {
-        eprintln!("Can't use email notification: {}", e);
-      }
+  /// Replace the `{{ var }}` templates, fails when a variable is not defined.
+  pub fn resolve_vars(&mut self, overrides: &HashMap<String, String>) -> Result<()> {
+    let unresolved = self.interpolate_vars(overrides);
+    if !unresolved.is_empty() {
+      let unresolved = unresolved
+        .iter()
+        .map(|(path, name)| format!("`{}` in {}", name, path.join(".")))
+        .collect::<Vec<_>>();
+      bail!("Unresolved variables: {}", unresolved.join(", "));
+    }
+    Ok(())
+  }
+
+  pub fn task_working_dir<'a>(&'a self, task: &'a Task) -> &'a Option<String> {
+    task
+      .working_dir()
+      .as_ref()
+      .map_or(self.working_dir(), |_| task.working_dir())
+  }
+
+  pub fn task_stdout<'a>(&'a self, task: &'a Task) -> &'a Option<String> {
+    task
+      .stdout()
+      .as_ref()
+      .map_or(self.stdout(), |_| task.stdout())
+  }
+
+  pub fn task_stderr<'a>(&'a self, task: &'a Task) -> &'a Option<String> {
+    task
+      .stderr()
+      .as_ref()
+      .map_or(self.stderr(), |_| task.stderr())
+  }
+
+  pub fn grace_period(&self) -> Duration {
+    self
+      .grace_period
+      .unwrap_or_else(self::de::default_grace_period)
+  }
+
+  pub fn task_timeout(&self, task: &Task) -> Option<Duration> {
+    task.timeout().or(self.timeout)
+  }
+
+  pub fn task_grace_period(&self, task: &Task) -> Duration {
+    task.grace_period().unwrap_or_else(|| self.grace_period())
+  }
+
+  /// The shell executing the commands of the task, `sh -c` by default. `None` when the commands are
+  /// executed without shell (`shell: none`).
+  pub fn task_shell(&self, task: &Task) -> Option<Vec<String>> {
+    let shell = task
+      .shell()
+      .as_ref()
+      .or(self.shell.as_ref())
+      .map(|shell| shell.args())
+      .unwrap_or_else(|| vec!["sh".to_string(), "-c".to_string()]);
+    Some(shell).filter(|shell| shell != &["none"])
+  }
+
+  /// Whether the commands of the task are executed one by one, with `mode: steps` or without shell.
+  /// A task without commands runs an empty shell line like in the `chain` mode.
+  pub fn task_runs_steps(&self, task: &Task) -> bool {
+    (task.mode() == &CommandsMode::Steps || self.task_shell(task).is_none())
+      && !task.commands().is_empty()
+  }
+
+  /// The commands written as a line in the tasks executed without shell, with their task id and
+  /// their index. A line would be split on spaces, they must be lists of arguments.
+  pub fn lines_without_shell(&self) -> Vec<(&String, usize)> {
+    let mut lines = self
+      .tasks
+      .iter()
+      .filter(|(_, task)| self.task_shell(task).is_none())
+      .flat_map(|(id, task)| {
+        task
+          .commands()
+          .iter()
+          .enumerate()
+          .filter(|(_, command)| matches!(command.command(), CommandArgs::Line(_)))
+          .map(move |(i, _)| (id, i))
+      })
+      .collect::<Vec<_>>();
+    lines.sort();
+    lines
+  }
+
+  /// The tasks checked by a condition without being a dependency of its task, with the id of the
+  /// task. Their status would not be known yet when the condition is checked.
+  pub fn conditions_outside_dependencies(&self) -> Vec<(&String, &String)> {
+    let mut tasks = self
+      .tasks
+      .iter()
+      .filter_map(|(id, task)| Some((id, task, task.condition().as_ref()?)))
+      .flat_map(|(id, task, condition)| {
+        condition
+          .tasks()
+          .into_iter()
+          .filter(|prev| !task.depends_on().contains(prev))
+          .map(move |prev| (id, prev))
+      })
+      .collect::<Vec<_>>();
+    tasks.sort();
+    tasks
+  }
+
+  /// The program and the arguments executing the commands of the task, or only its command `step`
+  /// when it runs steps.
+  pub fn task_argv(&self, task: &Task, step: Option<usize>) -> Vec<String> {
+    let command = match step {
+      Some(step) => task.commands()[step].command().clone(),
+      None => CommandArgs::Line(task.full_command()),
     };
+    self.shell_argv(task, &command)
+  }
+
+  /// The program and the arguments executing a command with the shell of the task.
+  pub fn shell_argv(&self, task: &Task, command: &CommandArgs) -> Vec<String> {
+    match self.task_shell(task) {
+      Some(mut shell) => {
+        shell.push(command.line());
+        shell
+      }
+      None => command.args(),
+    }
   }
+}
+
+impl Notification {
+  pub fn when(&self) -> &WhenNotify {
+    &self.when
+  }
+
+  pub fn messages(&self) -> &Messages {
+    &self.messages
+  }
+
+  pub async fn notify_task_end(&self, task: &Task, exit: TaskExit) {
+    if !self.when().should_notify(&WhenNotify::TaskEnd) {
+      return;
+    }
 
-  pub async fn notify_all_tasks_end(&self, success: i32, failures: i32, failed: bool) {
+    let event = task_end_event(self.messages().task_end(), task, &exit);
+    crate::notification::dispatch(self.notifiers(), &event).await;
+  }
+
+  pub async fn notify_all_tasks_end(&self, resume: &Resume, failed: bool) {
     if !self.when().should_notify(&WhenNotify::End) {
       return;
     }
-    let msg = if !failed {
+    let msg = if resume.interrupted > 0 {
+      self.messages().interrupted()
+    } else if !failed {
       self.messages().all_tasks_end()
     } else {
       self.messages().task_failed()
     };
-    let msg = crate::notification::replace_templates(msg);
-    let msg = msg.replace("{resume.success}", &format!("{}", success));
-    let msg = msg.replace("{resume.failures}", &format!("{}", failures));
-
-    if let Some(print) = self.print().notify(&WhenNotify::End) {
-      if let Err(e) = crate::notification::notification_print(&print, msg.as_str()) {
-        eprintln!("Can't use print notification: {}", e);
-      }
-    }
+    let values = vec![
+      ("resume.success", format!("{}", resume.success)),
+      ("resume.failures", format!("{}", resume.failures)),
+      ("resume.skipped", format!("{}", resume.skipped)),
+      ("resume.interrupted", format!("{}", resume.interrupted)),
+    ];
+
+    let failed = failed || resume.failures > 0 || resume.interrupted > 0;
+    let event = Event::end(msg, to_values(values)).with_failure(failed);
+    crate::notification::dispatch(self.notifiers(), &event).await;
+  }
+}
 
-    if let Some(slack) = self.slack().notify(&WhenNotify::End) {
-      if let Err(e) = crate::notification::post_slack(&slack, msg.as_str()) {
-        eprintln!("Can't use slack notification: {}", e);
-      }
-    }
+/// The event of the end of a task, its message is the `template` rendered with the placeholders.
+pub fn task_end_event(template: &str, task: &Task, exit: &TaskExit) -> Event {
+  let short_cmd = task.short_command();
+  let id = if task.id().len() > 0 {
+    task.id()
+  } else {
+    &short_cmd
+  };
+  let failed_step = exit
+    .failed_step()
+    .and_then(|i| task.commands().get(i))
+    .map(|command| command.command().line());
+  let steps = exit
+    .steps()
+    .iter()
+    .zip(task.commands())
+    .map(|(step, command)| {
+      let duration = Duration::from_millis(step.duration().as_millis() as u64);
+      format!(
+        "`{}` {} in {:?}",
+        command.command(),
+        step.status(),
+        duration
+      )
+    })
+    .collect::<Vec<_>>();
+  let values = vec![
+    ("task.id", id.clone()),
+    ("task.full_cmd", task.full_command()),
+    ("task.short_cmd", short_cmd.clone()),
+    ("task.status_code", format!("{}", exit.status())),
+    ("task.timed_out", format!("{}", exit.timed_out())),
+    ("task.attempt", format!("{}", exit.attempt())),
+    ("task.max_attempts", format!("{}", task.max_attempts())),
+    ("task.failed_step", failed_step.unwrap_or_default()),
+    ("task.steps", steps.join(", ")),
+  ];
+
+  Event::task_end(template, to_values(values))
+    .with_failure(!exit.success())
+    .with_status(exit.status())
+}
 
-    if let Some(email) = self.email().notify(&WhenNotify::End) {
-      if let Err(e) = crate::notification::notification_email(&email, msg.as_str()).await {
-        eprintln!("Can't use email notification: {}", e);
-      }
-    };
-  }
+fn to_values(values: Vec<(&str, String)>) -> Vec<(String, String)> {
+  values
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect()
 }
 
 impl Slack {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
   pub fn url(&self) -> &String {
     &self.url
   }
@@ -285,6 +811,10 @@ impl Slack {
 }
 
 impl Discord {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
   pub fn url(&self) -> &String {
     &self.url
   }
@@ -295,12 +825,207 @@ impl Discord {
 }
 
 impl Print {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
   pub fn output(&self) -> &String {
     &self.output
   }
 }
 
+impl Hook {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn command(&self) -> &CommandArgs {
+    &self.command
+  }
+
+  /// How long the command can run before being killed, 30 seconds by default.
+  pub fn timeout(&self) -> Duration {
+    self.timeout.unwrap_or_else(self::de::default_hook_timeout)
+  }
+}
+
+impl Teams {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn title(&self) -> &Option<String> {
+    &self.title
+  }
+}
+
+impl Mattermost {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn channel(&self) -> &Option<String> {
+    &self.channel
+  }
+
+  pub fn username(&self) -> &Option<String> {
+    &self.username
+  }
+
+  pub fn icon_url(&self) -> &Option<String> {
+    &self.icon_url
+  }
+
+  pub fn icon_emoji(&self) -> &Option<String> {
+    &self.icon_emoji
+  }
+}
+
+impl GoogleChat {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+}
+
+impl Telegram {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn api_url(&self) -> &String {
+    &self.api_url
+  }
+
+  pub fn bot_token(&self) -> &String {
+    &self.bot_token
+  }
+
+  pub fn chat_id(&self) -> &String {
+    &self.chat_id
+  }
+}
+
+impl Matrix {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn homeserver(&self) -> &String {
+    &self.homeserver
+  }
+
+  pub fn room_id(&self) -> &String {
+    &self.room_id
+  }
+
+  pub fn access_token(&self) -> &String {
+    &self.access_token
+  }
+}
+
+impl Ntfy {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn token(&self) -> &Option<String> {
+    &self.token
+  }
+
+  pub fn title(&self) -> &Option<String> {
+    &self.title
+  }
+
+  /// The priority of the message, from 1 to 5. Failures are `urgent` (5) by default, the others
+  /// use the `default` one (3).
+  pub fn priority(&self, failed: bool) -> u8 {
+    if failed {
+      self.failure_priority.unwrap_or(5)
+    } else {
+      self.priority.unwrap_or(3)
+    }
+  }
+}
+
+impl Gotify {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn token(&self) -> &String {
+    &self.token
+  }
+
+  pub fn title(&self) -> &Option<String> {
+    &self.title
+  }
+
+  /// The priority of the message, from 0 to 10. Failures are 8 by default, the others 5.
+  pub fn priority(&self, failed: bool) -> u8 {
+    if failed {
+      self.failure_priority.unwrap_or(8)
+    } else {
+      self.priority.unwrap_or(5)
+    }
+  }
+}
+
+impl Webhook {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn method(&self) -> &String {
+    &self.method
+  }
+
+  pub fn headers(&self) -> &BTreeMap<String, String> {
+    &self.headers
+  }
+
+  pub fn auth(&self) -> &Option<WebhookAuth> {
+    &self.auth
+  }
+
+  /// The JSON template of the body, its strings accept the placeholders.
+  pub fn body(&self) -> &serde_json::Value {
+    &self.body
+  }
+
+  pub fn format(&self) -> &WebhookFormat {
+    &self.format
+  }
+}
+
 impl Mail {
+  pub fn when(&self) -> &Option<WhenNotify> {
+    &self.when
+  }
+
   pub fn from(&self) -> &MailAddress {
     &self.from
   }
@@ -337,60 +1062,67 @@ impl Mail {
   }
 }
 
-impl Default for OnFailure {
-  fn default() -> OnFailure {
-    OnFailure::Continue
+impl Include {
+  pub fn path(&self) -> &String {
+    match self {
+      Include::Path(path) => path,
+      Include::Namespaced { path, .. } => path,
+    }
   }
-}
 
-impl WhenNotify {
-  pub fn should_notify(&self, when: &WhenNotify) -> bool {
-    self != &WhenNotify::Never && (self == &WhenNotify::Always || self == when)
+  pub fn namespace(&self) -> Option<&String> {
+    match self {
+      Include::Path(_) => None,
+      Include::Namespaced { namespace, .. } => namespace.as_ref(),
+    }
   }
 }
 
-trait When {
-  fn when(&self) -> &Option<WhenNotify>;
+impl LogRetention {
+  pub fn keep_last(&self) -> usize {
+    self.keep_last
+  }
 }
 
-impl When for Print {
-  fn when(&self) -> &Option<WhenNotify> {
-    &self.when
+impl Retry {
+  pub fn attempts(&self) -> u32 {
+    self.attempts
   }
-}
 
-impl When for Slack {
-  fn when(&self) -> &Option<WhenNotify> {
-    &self.when
+  pub fn delay(&self) -> Duration {
+    self.delay.unwrap_or(Duration::ZERO)
   }
-}
-impl When for Discord {
-  fn when(&self) -> &Option<WhenNotify> {
-    &self.when
+
+  /// Whether a task which ended with `exit` on its `attempt` (starting at 1) should be spawned again.
+  pub fn should_retry(&self, exit: &TaskExit, attempt: u32) -> bool {
+    if exit.success() || attempt >= self.attempts {
+      return false;
+    }
+    self.on_exit_codes.is_empty()
+      || exit
+        .status()
+        .code()
+        .is_some_and(|code| self.on_exit_codes.contains(&code))
   }
-}
 
-impl When for Mail {
-  fn when(&self) -> &Option<WhenNotify> {
-    &self.when
+  /// The time to wait before spawning the attempt following `attempt`.
+  pub fn delay_after(&self, attempt: u32) -> Duration {
+    match self.backoff {
+      Backoff::Fixed => self.delay(),
+      Backoff::Exponential => self.delay() * 2u32.saturating_pow(attempt.saturating_sub(1)),
+    }
   }
 }
 
-trait Notify {
-  fn notify(&self, when: &WhenNotify) -> Self;
+impl Default for OnFailure {
+  fn default() -> OnFailure {
+    OnFailure::Continue
+  }
 }
 
-impl<T: Clone + When> Notify for Option<T> {
-  fn notify(&self, state: &WhenNotify) -> Self {
-    if let Some(it) = self {
-      if let Some(when) = it.when() {
-        if !when.should_notify(state) {
-          return None;
-        }
-      }
-      return Some(it.clone());
-    }
-    None
+impl WhenNotify {
+  pub fn should_notify(&self, when: &WhenNotify) -> bool {
+    self != &WhenNotify::Never && (self == &WhenNotify::Always || self == when)
   }
 }
 
@@ -406,6 +1138,10 @@ impl Messages {
   pub fn task_failed(&self) -> &String {
     &self.task_failed
   }
+
+  pub fn interrupted(&self) -> &String {
+    &self.interrupted
+  }
 }
 
 impl Default for Messages {
@@ -418,6 +1154,7 @@ impl Default for Messages {
       task_failed: String::from(
         "Tasks ended prematurely. Got {resume.success} success and {resume.failures} failure. Contains one critical failure.",
       ),
+      interrupted: self::de::default_interrupted_message(),
     }
   }
 }
@@ -469,6 +1206,17 @@ mod test {
   const NOTIFICATION_YAML: &str = "tests/resources/notification.yml";
   const CONCURRENCY_YAML: &str = "tests/resources/concurrency.yml";
   const ON_FAILURE_YAML: &str = "tests/resources/on_failure.yml";
+  const TIMEOUT_YAML: &str = "tests/resources/timeout.yml";
+  const RETRY_YAML: &str = "tests/resources/retry.yml";
+  const SKIP_DEPENDENTS_YAML: &str = "tests/resources/skip_dependents.yml";
+  const ENV_YAML: &str = "tests/resources/env.yml";
+  const OUTPUT_YAML: &str = "tests/resources/output.yml";
+  const LOGS_YAML: &str = "tests/resources/logs.yml";
+  const VARS_YAML: &str = "tests/resources/vars.yml";
+  const MATRIX_YAML: &str = "tests/resources/matrix.yml";
+  const STEPS_YAML: &str = "tests/resources/steps.yml";
+  const SHELL_YAML: &str = "tests/resources/shell.yml";
+  const CONDITION_YAML: &str = "tests/resources/condition.yml";
 
   #[test]
   pub fn get_no_tasks() -> anyhow::Result<()> {
@@ -580,7 +1328,7 @@ mod test {
       smtp: expected_smtp,
       when: None,
     };
-    let email = notification.email();
+    let email = &notification.mail;
 
     assert!(email.is_some());
     assert_eq!(email.clone(), Some(expected_mail));
@@ -752,10 +1500,25 @@ mod test {
       discord: Some(discord),
       print: Some(print),
       mail: Some(email),
+      teams: None,
+      mattermost: None,
+      google_chat: None,
+      telegram: None,
+      matrix: None,
+      ntfy: None,
+      gotify: None,
+      webhook: None,
+      hook: None,
       when: WhenNotify::Always,
       messages: Messages::default(),
     };
 
+    let names = notification
+      .notifiers()
+      .iter()
+      .map(|notifier| notifier.name())
+      .collect::<Vec<_>>();
+    assert_eq!(names, vec!["print", "slack", "discord", "email"]);
     assert_eq!(*config.notification(), Some(notification));
     assert_eq!(config.concurrency(), -1);
     assert_eq!(config.tasks().len(), 5);
@@ -813,6 +1576,409 @@ mod test {
     assert_eq!(config.tasks().get(&"e".to_string()), Some(&e));
   }
 
+  #[test]
+  fn timeout_yaml() {
+    let yaml = fs::read_to_string(TIMEOUT_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let a = config.tasks().get("a").unwrap();
+    let b = config.tasks().get("b").unwrap();
+    let c = config.tasks().get("c").unwrap();
+
+    assert_eq!(config.timeout, Some(Duration::from_secs(60)));
+    assert_eq!(config.grace_period(), Duration::from_secs(5));
+    assert_eq!(a.timeout(), Some(Duration::from_secs(1)));
+    assert_eq!(a.grace_period(), Some(Duration::from_millis(500)));
+    assert_eq!(config.task_timeout(a), Some(Duration::from_secs(1)));
+    assert_eq!(config.task_grace_period(a), Duration::from_millis(500));
+    assert_eq!(config.task_timeout(b), Some(Duration::from_secs(90)));
+    assert_eq!(config.task_grace_period(b), Duration::from_secs(5));
+    assert_eq!(config.task_timeout(c), Some(Duration::from_secs(60)));
+    assert_eq!(Config::default().grace_period(), Duration::from_secs(10));
+    assert!(Config::from_str("timeout: 10 minutes").is_err());
+    let err = Config::from_str("timeout: 99999999999999999d").unwrap_err();
+    assert!(format!("{:#}", err).contains("The duration `99999999999999999d` is too large"));
+    assert!(Config::from_str("timeout: 18446744073709551615s 1s").is_err());
+  }
+
+  #[test]
+  fn retry_yaml() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    let yaml = fs::read_to_string(RETRY_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let a = config.tasks().get("a").unwrap();
+    let b = config.tasks().get("b").unwrap();
+    let c = config.tasks().get("c").unwrap();
+
+    let retry_a = Retry {
+      attempts: 3,
+      delay: Some(Duration::from_millis(200)),
+      backoff: Backoff::Exponential,
+      on_exit_codes: vec![3],
+    };
+    let retry_b = Retry {
+      attempts: 2,
+      delay: None,
+      backoff: Backoff::Fixed,
+      on_exit_codes: vec![],
+    };
+    assert_eq!(a.retry(), &Some(retry_a.clone()));
+    assert_eq!(b.retry(), &Some(retry_b.clone()));
+    assert_eq!(c.retry(), &None);
+    assert_eq!(a.max_attempts(), 3);
+    assert_eq!(c.max_attempts(), 1);
+
+    let exit =
+      |code: i32, attempt: u32| TaskExit::new(ExitStatus::from_raw(code << 8), false, attempt);
+    assert!(retry_a.should_retry(&exit(3, 1), 1));
+    assert!(retry_a.should_retry(&exit(3, 2), 2));
+    assert!(!retry_a.should_retry(&exit(3, 3), 3));
+    assert!(!retry_a.should_retry(&exit(1, 1), 1));
+    assert!(!retry_a.should_retry(&exit(0, 1), 1));
+    assert!(retry_b.should_retry(&exit(127, 1), 1));
+    assert!(!retry_b.should_retry(&exit(127, 2), 2));
+
+    assert_eq!(retry_a.delay_after(1), Duration::from_millis(200));
+    assert_eq!(retry_a.delay_after(2), Duration::from_millis(400));
+    assert_eq!(retry_a.delay_after(3), Duration::from_millis(800));
+    assert_eq!(retry_b.delay_after(3), Duration::ZERO);
+  }
+
+  #[test]
+  fn skip_dependents_yaml() {
+    let yaml = fs::read_to_string(SKIP_DEPENDENTS_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+
+    assert_eq!(config.on_failure(), &OnFailure::SkipDependents);
+    assert_eq!(config.tasks().len(), 5);
+    assert_eq!(
+      config
+        .notification()
+        .as_ref()
+        .unwrap()
+        .messages()
+        .all_tasks_end(),
+      "All tasks ended. Got {resume.success} success, {resume.failures} failure and {resume.skipped} skipped."
+    );
+  }
+
+  #[test]
+  fn env_yaml() {
+    let yaml = fs::read_to_string(ENV_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let a = config.tasks().get("a").unwrap();
+    let b = config.tasks().get("b").unwrap();
+
+    let env = |vars: &[(&str, &str)]| -> HashMap<String, String> {
+      vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    };
+    assert_eq!(
+      config.env(),
+      &env(&[("GREETING", "Hello"), ("NAME", "runtasktic")])
+    );
+    assert_eq!(config.env_file(), &Some("env.env".to_string()));
+    assert_eq!(a.env(), &env(&[("NAME", "task a")]));
+    assert_eq!(
+      b.env(),
+      &env(&[("GREETING", "Bonjour"), ("NAME", "task b")])
+    );
+    assert_eq!(config.task_working_dir(a), &None);
+    assert_eq!(config.task_working_dir(b), &Some("/tmp".to_string()));
+    assert_eq!(config.task_stdout(a), &None);
+    assert_eq!(
+      config.task_stdout(b),
+      &Some("/tmp/runtasktic-b.out".to_string())
+    );
+    assert_eq!(config.task_stderr(a), &Some("runtasktic.err".to_string()));
+    assert_eq!(config.task_stderr(b), &Some("none".to_string()));
+  }
+
+  #[test]
+  fn output_yaml() {
+    let yaml = fs::read_to_string(OUTPUT_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+
+    assert_eq!(config.output(), &OutputMode::Prefixed);
+    assert!(config.output_timestamp());
+    assert_eq!(config.tasks().len(), 3);
+
+    let config = Config::from_str(&fs::read_to_string(SAMPLE_YAML).unwrap()).unwrap();
+    assert_eq!(config.output(), &OutputMode::Inherit);
+    assert!(!config.output_timestamp());
+  }
+
+  #[test]
+  fn logs_yaml() {
+    let yaml = fs::read_to_string(LOGS_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let b = config.tasks().get("b").unwrap();
+
+    assert_eq!(config.log_retention(), &Some(LogRetention { keep_last: 2 }));
+    assert_eq!(
+      config.task_stdout(b),
+      &Some("/tmp/runtasktic/logs/{task.id}-{date}_{time}.out".to_string())
+    );
+    assert_eq!(
+      config.task_stderr(b),
+      &Some("/tmp/runtasktic/logs/{task.id}-{date}_{time}.err".to_string())
+    );
+  }
+
+  #[test]
+  fn vars_yaml() {
+    let yaml = fs::read_to_string(VARS_YAML).unwrap();
+    let mut config = Config::from_str(yaml.as_str()).unwrap();
+
+    let err = config.clone().resolve_vars(&HashMap::new()).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Unresolved variables: `target` in tasks.deploy.commands.0"
+    );
+
+    let overrides = HashMap::from([
+      ("target".to_string(), "prod".to_string()),
+      ("version".to_string(), "2.0.0".to_string()),
+    ]);
+    config.resolve_vars(&overrides).unwrap();
+    let deploy = config.tasks().get("deploy").unwrap();
+    assert_eq!(
+      config.tasks().get("build").unwrap().full_command(),
+      "echo build 2.0.0 && echo 2.0.0 > /tmp/runtasktic/version"
+    );
+    assert_eq!(deploy.full_command(), "echo deploy 2.0.0 to prod");
+    assert_eq!(deploy.working_dir(), &Some("/tmp/runtasktic".to_string()));
+    assert_eq!(
+      deploy.condition().as_ref().unwrap().command(),
+      Some(&"test -d /tmp/runtasktic".to_string())
+    );
+    assert_eq!(
+      config.stdout(),
+      &Some("/tmp/runtasktic/{task.id}.out".to_string())
+    );
+  }
+
+  #[test]
+  fn matrix_yaml() {
+    let yaml = fs::read_to_string(MATRIX_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let depends_on = |id: &str| {
+      let mut depends_on = config.tasks().get(id).unwrap().depends_on().clone();
+      depends_on.sort();
+      depends_on
+    };
+
+    let mut ids = config.tasks().keys().cloned().collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(
+      ids,
+      vs(&[
+        "build",
+        "check",
+        "deploy[region=eu,size=l]",
+        "deploy[region=eu,size=s]",
+        "deploy[region=us,size=l]",
+        "deploy[region=us,size=s]",
+        "report",
+        "smoke"
+      ])
+    );
+    let deploy = config.tasks().get("deploy[region=eu,size=s]").unwrap();
+    assert_eq!(deploy.depends_on(), &vs(&["build"]));
+    assert_eq!(deploy.env().get("region"), Some(&"eu".to_string()));
+    assert_eq!(deploy.env().get("size"), Some(&"s".to_string()));
+    assert_eq!(
+      depends_on("check"),
+      vs(&["deploy[region=eu,size=l]", "deploy[region=eu,size=s]"])
+    );
+    assert_eq!(depends_on("smoke"), vs(&["deploy[region=us,size=l]"]));
+    assert_eq!(depends_on("report").len(), 4);
+
+    let yaml = "tasks:\n  a:\n    matrix:\n      region: []\n";
+    assert!(Config::from_str(yaml).is_err());
+  }
+
+  #[test]
+  fn steps_yaml() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    let yaml = fs::read_to_string(STEPS_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let build = config.tasks().get("build").unwrap();
+    let chain = config.tasks().get("chain").unwrap();
+    let step = |code: i32| StepExit::new(ExitStatus::from_raw(code << 8), Duration::from_secs(1));
+
+    assert!(config.task_runs_steps(build));
+    assert!(!config.task_runs_steps(chain));
+    assert_eq!(chain.full_command(), "echo chain && { exit 3; } || true");
+    assert_eq!(build.next_step(&[]), Some(0));
+    assert_eq!(build.next_step(&[step(0), step(3)]), Some(2));
+    assert_eq!(build.next_step(&[step(0), step(3), step(4)]), None);
+
+    let exit = TaskExit::new(ExitStatus::from_raw(4 << 8), false, 1);
+    let failed = exit
+      .clone()
+      .with_steps(build, vec![step(0), step(3), step(4)]);
+    assert_eq!(failed.failed_step(), Some(2));
+    assert_eq!(failed.status().code(), Some(4));
+    let success = exit
+      .clone()
+      .with_steps(build, vec![step(0), step(3), step(0), step(0)]);
+    assert_eq!(success.failed_step(), None);
+    assert!(success.success());
+
+    // SIGTERM received during a step with `continue_on_error`, the next steps never ran
+    let sigterm = StepExit::new(ExitStatus::from_raw(15), Duration::from_secs(1));
+    let interrupted = exit
+      .clone()
+      .with_interrupted(true)
+      .with_steps(build, vec![step(0), sigterm]);
+    assert_eq!(interrupted.failed_step(), None);
+    assert_eq!(interrupted.status().signal(), Some(15));
+    assert!(!interrupted.success());
+    let incomplete = exit.with_steps(build, vec![step(0), step(0)]);
+    assert!(!incomplete.success());
+  }
+
+  #[test]
+  fn task_end_message() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    let task = Task::new("a", vec!["sleep 10".to_string()], vec![], None);
+    let template = Messages::default().task_end().clone();
+    let timeout = format!("{} (timed out {{task.timed_out}})", template);
+    let exit = TaskExit::new(ExitStatus::from_raw(3 << 8), false, 1);
+    let event = task_end_event(&template, &task, &exit);
+    assert_eq!(
+      event.message(),
+      "Task a ended with status code exit status: 3"
+    );
+    assert!(event.failed());
+
+    let killed = TaskExit::new(ExitStatus::from_raw(15), false, 1);
+    let event = task_end_event(&template, &task, &killed);
+    assert_eq!(
+      event.message(),
+      "Task a ended with status code signal: 15 (SIGTERM)"
+    );
+
+    let timed_out = TaskExit::new(ExitStatus::from_raw(15), true, 1);
+    let event = task_end_event(&timeout, &task, &timed_out);
+    assert_eq!(
+      event.message(),
+      "Task a ended with status code signal: 15 (SIGTERM) (timed out true)"
+    );
+    assert_eq!(event.status().and_then(|s| s.signal()), Some(15));
+  }
+
+  #[test]
+  fn shell_yaml() {
+    let yaml = fs::read_to_string(SHELL_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let task = |id: &str| config.tasks().get(id).unwrap();
+
+    assert_eq!(
+      config.task_argv(task("pipe"), None),
+      vs(&["bash", "-euo", "pipefail", "-c", "false | true"])
+    );
+    assert_eq!(config.task_shell(task("direct")), None);
+    let line = Config::from_str("tasks:\n  a:\n    shell: none\n    commands: [echo \"a b\"]\n");
+    assert_eq!(
+      line.unwrap_err().to_string(),
+      "The command 1 of the task a must be a list of arguments, the task runs without shell"
+    );
+    assert!(config.task_runs_steps(task("direct")));
+    assert_eq!(
+      config.task_argv(task("direct"), Some(0)),
+      vs(&["touch", "/tmp/runtasktic/my file"])
+    );
+    assert_eq!(
+      config.task_argv(task("direct"), Some(1)),
+      vs(&["echo", "done"])
+    );
+    assert_eq!(
+      config.task_argv(task("python"), None),
+      vs(&["python3", "-c", "print(\"it's python\")"])
+    );
+    assert_eq!(
+      config.task_argv(task("quoted"), None),
+      vs(&["bash", "-euo", "pipefail", "-c", "echo 'it'\\''s' quoted"])
+    );
+    assert_eq!(
+      Config::default().task_argv(task("pipe"), None),
+      vs(&["sh", "-c", "false | true"])
+    );
+  }
+
+  #[test]
+  fn condition_yaml() {
+    use crate::fst::TaskStatus;
+
+    let yaml = fs::read_to_string(CONDITION_YAML).unwrap();
+    let config = Config::from_str(yaml.as_str()).unwrap();
+    let condition = |id: &str| config.tasks().get(id).unwrap().condition().clone().unwrap();
+    let env = |name: &str| config.env().get(name).cloned();
+
+    let build = condition("build");
+    assert_eq!(build.command(), Some(&"test -d /tmp".to_string()));
+    assert!(build.matches_env(|_| None));
+    assert!(build.matches_results(|_| None));
+
+    let deploy = condition("deploy");
+    assert_eq!(deploy.command(), None);
+    assert!(deploy.matches_env(env));
+    assert!(!deploy.matches_env(|_| Some("dev".to_string())));
+    assert!(deploy.matches_results(|_| Some(TaskStatus::Done)));
+    assert!(!deploy.matches_results(|_| Some(TaskStatus::Skipped)));
+
+    let cleanup = condition("cleanup");
+    assert_eq!(cleanup.command(), Some(&"test -n \"$TARGET\"".to_string()));
+    assert!(cleanup.matches_env(env));
+    assert!(!cleanup.matches_env(|_| Some(String::new())));
+    assert!(cleanup.matches_results(|_| Some(TaskStatus::Failed)));
+    assert!(!cleanup.matches_results(|_| Some(TaskStatus::Done)));
+
+    let on_skip = |id: &str| config.task_on_skip(config.tasks().get(id).unwrap()).clone();
+    assert_eq!(on_skip("deploy"), OnSkip::Continue);
+    assert_eq!(on_skip("cleanup"), OnSkip::SkipDependents);
+    assert_eq!(Config::default().on_skip, OnSkip::SkipDependents);
+  }
+
+  #[test]
+  fn json_schema() {
+    let schema = serde_json::to_value(Config::json_schema()).unwrap();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+
+    let mut fixtures = fs::read_dir("tests/resources")
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .filter(|path| path.extension().is_some_and(|ext| ext == "yml"))
+      .collect::<Vec<_>>();
+    fixtures.sort();
+    assert!(!fixtures.is_empty());
+    for fixture in fixtures {
+      let yaml: serde_json::Value =
+        serde_yaml::from_str(&fs::read_to_string(&fixture).unwrap()).unwrap();
+      let errors = validator
+        .iter_errors(&yaml)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+      assert_eq!(errors, Vec::<String>::new(), "{}", fixture.display());
+    }
+
+    let yaml = serde_json::json!({ "tasks": { "a": { "timeout": true } }, "concurrency": "a" });
+    assert_eq!(validator.iter_errors(&yaml).count(), 2);
+    let body =
+      serde_json::json!({ "alert": { "tags": ["ci", "{task.id}"], "priority": 2, "open": true } });
+    let yaml = serde_json::json!({ "notification": { "webhook": { "url": "http://localhost", "body": body } } });
+    assert_eq!(validator.iter_errors(&yaml).count(), 0);
+  }
+
   fn echo(msg: &str) -> String {
     format!("echo {}", msg)
   }
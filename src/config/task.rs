@@ -1,19 +1,124 @@
-use crate::config::OnFailure;
+use crate::config::{OnFailure, OnSkip, Retry};
+use crate::fst::TaskStatus;
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::time::Duration;
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Task {
   #[serde(skip)]
   pub(super) id: String,
   #[serde(default)]
-  commands: Vec<String>,
+  commands: Vec<TaskCommand>,
+  #[serde(default)]
+  mode: CommandsMode,
+  shell: Option<CommandArgs>,
   #[serde(default)]
   depends_on: Vec<String>,
   on_failure: Option<OnFailure>,
+  #[serde(rename = "if", alias = "when")]
+  condition: Option<Condition>,
+  on_skip: Option<OnSkip>,
+  #[serde(default, deserialize_with = "super::de::deserialize_duration")]
+  #[schemars(with = "Option<super::de::RawDuration>")]
+  timeout: Option<Duration>,
+  #[serde(default, deserialize_with = "super::de::deserialize_duration")]
+  #[schemars(with = "Option<super::de::RawDuration>")]
+  grace_period: Option<Duration>,
+  retry: Option<Retry>,
+  #[serde(default)]
+  env: HashMap<String, String>,
+  working_dir: Option<String>,
+  stdout: Option<String>,
+  stderr: Option<String>,
+  #[serde(default)]
+  matrix: BTreeMap<String, Vec<String>>,
+  #[serde(skip)]
+  matrix_values: BTreeMap<String, String>,
   #[serde(skip)]
   state: usize,
 }
 
+/// A command of a task, the mapping form sets the options of the command.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum TaskCommand {
+  Args(CommandArgs),
+  Step {
+    command: CommandArgs,
+    /// Run the next commands even if this one fails.
+    #[serde(default)]
+    continue_on_error: bool,
+  },
+}
+
+/// A command line for a shell, or a program followed by its arguments.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum CommandArgs {
+  Line(String),
+  Argv(Vec<String>),
+}
+
+/// The condition to run a task, checked when the task is ready to start. A string is a command.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum Condition {
+  Command(String),
+  Checks {
+    /// A command which must exit with 0, it is executed by the shell of the task.
+    command: Option<String>,
+    /// Environment variables which must be set and not empty, or have the given values.
+    #[serde(default)]
+    env: EnvCondition,
+    /// Dependencies which must have ended successfully.
+    #[serde(default)]
+    succeeded: Vec<String>,
+    /// Dependencies which must have failed.
+    #[serde(default)]
+    failed: Vec<String>,
+  },
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum EnvCondition {
+  Set(Vec<String>),
+  Equals(BTreeMap<String, String>),
+}
+
+/// How the commands of a task are executed.
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandsMode {
+  /// One shell line, the commands are joined with `&&`.
+  #[default]
+  Chain,
+  /// One process per command, each one starting when the previous one ends.
+  Steps,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TaskExit {
+  status: ExitStatus,
+  timed_out: bool,
+  attempt: u32,
+  steps: Vec<StepExit>,
+  failed_step: Option<usize>,
+  interrupted: bool,
+}
+
+/// The end of a command of a task executed in `steps` mode.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StepExit {
+  status: ExitStatus,
+  duration: Duration,
+}
+
 impl Task {
   pub fn new<S: Into<String>>(
     id: S,
@@ -23,9 +128,25 @@ impl Task {
   ) -> Task {
     Task {
       id: id.into(),
-      commands,
+      commands: commands
+        .into_iter()
+        .map(|command| TaskCommand::Args(CommandArgs::Line(command)))
+        .collect(),
+      mode: CommandsMode::Chain,
+      shell: None,
       depends_on,
       on_failure,
+      condition: None,
+      on_skip: None,
+      timeout: None,
+      grace_period: None,
+      retry: None,
+      env: HashMap::new(),
+      working_dir: None,
+      stdout: None,
+      stderr: None,
+      matrix: BTreeMap::new(),
+      matrix_values: BTreeMap::new(),
       state: 0,
     }
   }
@@ -33,26 +154,175 @@ impl Task {
   pub fn id(&self) -> &String {
     &self.id
   }
-  pub fn commands(&self) -> &Vec<String> {
+  pub fn commands(&self) -> &Vec<TaskCommand> {
     &self.commands
   }
   pub fn full_command(&self) -> String {
-    self.commands().join(" && ")
+    self
+      .commands()
+      .iter()
+      .map(|command| match command.continue_on_error() {
+        true => format!("{{ {}; }} || true", command.command()),
+        false => command.command().line(),
+      })
+      .collect::<Vec<_>>()
+      .join(" && ")
   }
   pub fn short_command(&self) -> String {
     if self.commands().len() > 0 {
-      let cmd = self.commands().first().unwrap();
+      let cmd = self.commands().first().unwrap().command().line();
       cmd.splitn(2, " ").next().unwrap().to_string()
     } else {
       format!("<unknonw short command>")
     }
   }
+  pub fn mode(&self) -> &CommandsMode {
+    &self.mode
+  }
+  pub fn shell(&self) -> &Option<CommandArgs> {
+    &self.shell
+  }
+  /// The index of the next step to execute after `steps`, `None` when the task ended.
+  pub fn next_step(&self, steps: &[StepExit]) -> Option<usize> {
+    if let Some(last) = steps.last()
+      && !last.status.success()
+      && !self.commands[steps.len() - 1].continue_on_error()
+    {
+      return None;
+    }
+    Some(steps.len()).filter(|next| *next < self.commands.len())
+  }
   pub fn depends_on(&self) -> &Vec<String> {
     &self.depends_on
   }
   pub fn on_failure(&self) -> &Option<OnFailure> {
     &self.on_failure
   }
+  /// The condition to run the task, it is skipped when the condition is false.
+  pub fn condition(&self) -> &Option<Condition> {
+    &self.condition
+  }
+  pub fn on_skip(&self) -> &Option<OnSkip> {
+    &self.on_skip
+  }
+  pub fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+  pub fn grace_period(&self) -> Option<Duration> {
+    self.grace_period
+  }
+  pub fn retry(&self) -> &Option<Retry> {
+    &self.retry
+  }
+  pub fn max_attempts(&self) -> u32 {
+    self
+      .retry
+      .as_ref()
+      .map(|retry| retry.attempts())
+      .unwrap_or(1)
+  }
+  pub fn env(&self) -> &HashMap<String, String> {
+    &self.env
+  }
+  pub fn working_dir(&self) -> &Option<String> {
+    &self.working_dir
+  }
+  pub fn stdout(&self) -> &Option<String> {
+    &self.stdout
+  }
+  pub fn stderr(&self) -> &Option<String> {
+    &self.stderr
+  }
+  pub fn matrix(&self) -> &BTreeMap<String, Vec<String>> {
+    &self.matrix
+  }
+  /// The values of the matrix combination of this task, when it comes from a matrix.
+  pub fn matrix_values(&self) -> &BTreeMap<String, String> {
+    &self.matrix_values
+  }
+  /// One task for each combination of the matrix values, their ids are `id[key=value,...]` and the
+  /// values are added to their environment variables.
+  pub(super) fn expand_matrix(&self) -> Result<Vec<Task>> {
+    let mut combinations = vec![BTreeMap::new()];
+    for (key, values) in self.matrix.iter() {
+      if values.is_empty() {
+        bail!(
+          "The matrix of the task {} has no values for {}",
+          self.id,
+          key
+        );
+      }
+      combinations = combinations
+        .into_iter()
+        .flat_map(|combination: BTreeMap<String, String>| {
+          values.iter().map(move |value| {
+            let mut combination = combination.clone();
+            combination.insert(key.clone(), value.clone());
+            combination
+          })
+        })
+        .collect();
+    }
+
+    Ok(
+      combinations
+        .into_iter()
+        .map(|values| {
+          let mut task = self.clone();
+          task.id = matrix_id(&self.id, &values);
+          task.env.extend(values.clone());
+          task.matrix = BTreeMap::new();
+          task.matrix_values = values;
+          task
+        })
+        .collect(),
+    )
+  }
+  /// Replace each task referenced by the dependencies and the condition with the tasks of `f`.
+  pub(super) fn map_depends_on<F: FnMut(&String) -> Vec<String>>(&mut self, mut f: F) {
+    self.depends_on = self.depends_on.iter().flat_map(&mut f).collect();
+    if let Some(Condition::Checks {
+      succeeded, failed, ..
+    }) = &mut self.condition
+    {
+      *succeeded = succeeded.iter().flat_map(&mut f).collect();
+      *failed = failed.iter().flat_map(&mut f).collect();
+    }
+  }
+  /// The fields accepting `{{ var }}` templates, with their path in the configuration.
+  pub(super) fn templates_mut(&mut self) -> Vec<(Vec<String>, &mut String)> {
+    let path = |keys: &[&str]| {
+      ["tasks", self.id.as_str()]
+        .iter()
+        .chain(keys)
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+    };
+    let mut fields = vec![];
+    for (i, command) in self.commands.iter_mut().enumerate() {
+      for field in command.command_mut().strings_mut() {
+        fields.push((path(&["commands", &i.to_string()]), field));
+      }
+    }
+    for (key, field) in [
+      ("working_dir", &mut self.working_dir),
+      ("stdout", &mut self.stdout),
+      ("stderr", &mut self.stderr),
+    ] {
+      if let Some(value) = field {
+        fields.push((path(&[key]), value));
+      }
+    }
+    match &mut self.condition {
+      Some(Condition::Command(command)) => fields.push((path(&["if"]), command)),
+      Some(Condition::Checks {
+        command: Some(command),
+        ..
+      }) => fields.push((path(&["if", "command"]), command)),
+      _ => {}
+    }
+    fields
+  }
   pub fn state(&self) -> usize {
     self.state
   }
@@ -60,3 +330,219 @@ impl Task {
     self.state = state
   }
 }
+
+/// The id of a matrix instance: `id[key=value,...]`.
+pub fn matrix_id(id: &str, values: &BTreeMap<String, String>) -> String {
+  let values = values
+    .iter()
+    .map(|(key, value)| format!("{}={}", key, value))
+    .collect::<Vec<_>>();
+  format!("{}[{}]", id, values.join(","))
+}
+
+/// Split a matrix reference `id[key=value,...]` in its task id and values, `None` without values.
+pub fn parse_matrix_id(id: &str) -> Option<(&str, BTreeMap<String, String>)> {
+  let (name, values) = id.strip_suffix(']')?.split_once('[')?;
+  let values = values
+    .split(',')
+    .map(|value| {
+      let (key, value) = value.split_once('=')?;
+      Some((key.trim().to_string(), value.trim().to_string()))
+    })
+    .collect::<Option<_>>()?;
+  Some((name, values))
+}
+
+impl TaskCommand {
+  pub fn command(&self) -> &CommandArgs {
+    match self {
+      TaskCommand::Args(command) | TaskCommand::Step { command, .. } => command,
+    }
+  }
+  fn command_mut(&mut self) -> &mut CommandArgs {
+    match self {
+      TaskCommand::Args(command) | TaskCommand::Step { command, .. } => command,
+    }
+  }
+  pub fn continue_on_error(&self) -> bool {
+    match self {
+      TaskCommand::Args(_) => false,
+      TaskCommand::Step {
+        continue_on_error, ..
+      } => *continue_on_error,
+    }
+  }
+}
+
+impl Condition {
+  pub fn command(&self) -> Option<&String> {
+    match self {
+      Condition::Command(command) => Some(command),
+      Condition::Checks { command, .. } => command.as_ref(),
+    }
+  }
+  /// The tasks whose results are checked.
+  pub fn tasks(&self) -> Vec<&String> {
+    match self {
+      Condition::Command(_) => vec![],
+      Condition::Checks {
+        succeeded, failed, ..
+      } => succeeded.iter().chain(failed).collect(),
+    }
+  }
+  /// Whether the environment variables match, `var` gives the value of a variable.
+  pub fn matches_env<F: Fn(&str) -> Option<String>>(&self, var: F) -> bool {
+    match self {
+      Condition::Command(_) => true,
+      Condition::Checks { env, .. } => match env {
+        EnvCondition::Set(names) => names
+          .iter()
+          .all(|name| var(name).is_some_and(|value| !value.is_empty())),
+        EnvCondition::Equals(values) => values
+          .iter()
+          .all(|(name, value)| var(name).as_ref() == Some(value)),
+      },
+    }
+  }
+  /// Whether the tasks ended as expected, `status` gives the status of a task.
+  pub fn matches_results<F: Fn(&str) -> Option<TaskStatus>>(&self, status: F) -> bool {
+    match self {
+      Condition::Command(_) => true,
+      Condition::Checks {
+        succeeded, failed, ..
+      } => {
+        succeeded
+          .iter()
+          .all(|id| status(id) == Some(TaskStatus::Done))
+          && failed
+            .iter()
+            .all(|id| status(id) == Some(TaskStatus::Failed))
+      }
+    }
+  }
+}
+
+impl Default for EnvCondition {
+  fn default() -> EnvCondition {
+    EnvCondition::Set(vec![])
+  }
+}
+
+impl CommandArgs {
+  /// The program and its arguments, a line is split on whitespaces.
+  pub fn args(&self) -> Vec<String> {
+    match self {
+      CommandArgs::Line(line) => line.split_whitespace().map(String::from).collect(),
+      CommandArgs::Argv(args) => args.clone(),
+    }
+  }
+  /// The line for a shell, the arguments are quoted when needed.
+  pub fn line(&self) -> String {
+    match self {
+      CommandArgs::Line(line) => line.clone(),
+      CommandArgs::Argv(args) => args
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" "),
+    }
+  }
+  fn strings_mut(&mut self) -> Vec<&mut String> {
+    match self {
+      CommandArgs::Line(line) => vec![line],
+      CommandArgs::Argv(args) => args.iter_mut().collect(),
+    }
+  }
+}
+
+impl fmt::Display for CommandArgs {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.line())
+  }
+}
+
+/// Quote an argument for a POSIX shell, unless it is only made of safe characters.
+fn quote(arg: &str) -> String {
+  let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+  if !arg.is_empty() && arg.chars().all(safe) {
+    arg.to_string()
+  } else {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+  }
+}
+
+impl TaskExit {
+  pub fn new(status: ExitStatus, timed_out: bool, attempt: u32) -> TaskExit {
+    TaskExit {
+      status,
+      timed_out,
+      attempt,
+      steps: vec![],
+      failed_step: None,
+      interrupted: false,
+    }
+  }
+
+  /// The task was stopped by a signal received by runtasktic.
+  pub fn with_interrupted(mut self, interrupted: bool) -> TaskExit {
+    self.interrupted = self.interrupted || interrupted;
+    self
+  }
+
+  /// The exit of a task executed in `steps` mode, it fails with the first step which failed without
+  /// `continue_on_error`. A task stopped before its last step keeps the status of the last step
+  /// executed and is interrupted. Nothing changes without steps.
+  pub fn with_steps(mut self, task: &Task, steps: Vec<StepExit>) -> TaskExit {
+    if steps.is_empty() {
+      return self;
+    }
+    self.failed_step = steps
+      .iter()
+      .zip(task.commands())
+      .position(|(step, command)| !step.status.success() && !command.continue_on_error());
+    let complete = steps.len() >= task.commands().len();
+    self.status = match self.failed_step {
+      Some(i) => steps[i].status,
+      None if !complete => steps[steps.len() - 1].status,
+      None => ExitStatus::from_raw(0),
+    };
+    self.interrupted = self.interrupted || (self.failed_step.is_none() && !complete);
+    self.steps = steps;
+    self
+  }
+
+  pub fn status(&self) -> ExitStatus {
+    self.status
+  }
+  pub fn timed_out(&self) -> bool {
+    self.timed_out
+  }
+  pub fn attempt(&self) -> u32 {
+    self.attempt
+  }
+  pub fn steps(&self) -> &Vec<StepExit> {
+    &self.steps
+  }
+  /// The index of the step which made the task fail.
+  pub fn failed_step(&self) -> Option<usize> {
+    self.failed_step
+  }
+  /// A task killed by its timeout or interrupted is a failure, even if it handled the signal
+  /// gracefully.
+  pub fn success(&self) -> bool {
+    self.status.success() && !self.timed_out && !self.interrupted
+  }
+}
+
+impl StepExit {
+  pub fn new(status: ExitStatus, duration: Duration) -> StepExit {
+    StepExit { status, duration }
+  }
+
+  pub fn status(&self) -> ExitStatus {
+    self.status
+  }
+  pub fn duration(&self) -> Duration {
+    self.duration
+  }
+}
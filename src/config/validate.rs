@@ -0,0 +1,626 @@
+use crate::config::{Config, include};
+use crate::fst::TaskFst;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// A finding of the validation, `line` and `column` start at 1.
+#[derive(Debug, PartialEq, Clone, serde::Serialize)]
+pub struct Diagnostic {
+  file: String,
+  line: usize,
+  column: usize,
+  severity: Severity,
+  message: String,
+}
+
+impl Diagnostic {
+  pub fn severity(&self) -> Severity {
+    self.severity
+  }
+}
+
+impl std::fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let severity = match self.severity {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    };
+    write!(
+      f,
+      "{}:{}:{}: {}: {}",
+      self.file, self.line, self.column, severity, self.message
+    )
+  }
+}
+
+/// Check the content of the configuration `file`. Relative paths are resolved from the current
+/// directory and the `vars` override the variables, like during a run.
+pub fn validate(file: &str, yaml: &str, vars: &HashMap<String, String>) -> Vec<Diagnostic> {
+  let locator = Locator::new(yaml);
+  let mut findings = Findings {
+    file,
+    locator: &locator,
+    includes: HashMap::new(),
+    diagnostics: vec![],
+  };
+
+  let mut unknown = vec![];
+  let deserializer = serde_yaml::Deserializer::from_str(yaml);
+  let config: Result<Config, _> =
+    serde_ignored::deserialize(deserializer, |path| unknown.push(path.to_string()));
+  let mut config = match config {
+    Ok(config) => config,
+    Err(e) => {
+      let (line, column) = e
+        .location()
+        .map(|location| (location.line(), location.column()))
+        .unwrap_or((1, 1));
+      findings.push_at(line, column, Severity::Error, e.to_string());
+      return findings.diagnostics;
+    }
+  };
+
+  for path in unknown {
+    let path = path
+      .split('.')
+      .filter(|key| *key != "?")
+      .collect::<Vec<_>>();
+    let key = path.last().unwrap_or(&"");
+    findings.push(&path, Severity::Warning, format!("Unknown key `{}`", key));
+  }
+
+  if let Err(e) = config.expand_matrices() {
+    findings.push(&["tasks"], Severity::Error, e.to_string());
+  }
+
+  // The tasks of the includes are needed to check dependencies, the env file is relative to the file
+  let mut config = if config.include().is_empty() && config.env_file().is_none() {
+    config
+  } else {
+    // Not with `Config::from_str`, its checks are reported below at the key of the task
+    let included = include::read(Path::new(file)).and_then(|yaml| {
+      let mut config: Config = serde_yaml::from_str(&yaml)?;
+      config.expand_matrices()?;
+      Ok(config)
+    });
+    match included {
+      Ok(config) => {
+        findings.locate_includes();
+        config
+      }
+      Err(e) => {
+        findings.push(&["include"], Severity::Error, format!("{:#}", e));
+        config
+      }
+    }
+  };
+
+  for (path, name) in config.interpolate_vars(vars) {
+    findings.push(
+      &path,
+      Severity::Error,
+      format!("Unresolved variable `{}`", name),
+    );
+  }
+  check_tasks(&config, &mut findings);
+  check_paths(&config, &mut findings);
+  if let Some(notification) = config.notification() {
+    check_notification(notification, &mut findings);
+  }
+
+  // The findings of the file first, then the ones of its includes
+  findings
+    .diagnostics
+    .sort_by_key(|d| (d.file != file, d.file.clone(), d.line, d.column));
+  findings.diagnostics
+}
+
+fn check_tasks(config: &Config, findings: &mut Findings) {
+  if config.concurrency() == 0 {
+    findings.push(
+      &["concurrency"],
+      Severity::Error,
+      "The concurrency must be positive, or negative for no limit".to_string(),
+    );
+  }
+  if config.tasks().is_empty() {
+    findings.push(&["tasks"], Severity::Error, "No task to run".to_string());
+    return;
+  }
+
+  let mut ids = config.tasks().keys().collect::<Vec<_>>();
+  ids.sort();
+  let states: HashMap<&String, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+  let mut graph = TaskFst::new();
+  for id in ids.iter() {
+    graph.add_state(id.as_str());
+  }
+
+  for id in ids.iter() {
+    let task = &config.tasks()[*id];
+    if task.commands().is_empty()
+      || task
+        .commands()
+        .iter()
+        .all(|c| c.command().line().trim().is_empty())
+    {
+      findings.push(
+        &["tasks", id, "commands"],
+        Severity::Warning,
+        format!("The task {} has no commands", id),
+      );
+    }
+    for (_, i) in config.lines_without_shell().iter().filter(|(t, _)| t == id) {
+      findings.push(
+        &["tasks", id, "commands", &i.to_string()],
+        Severity::Error,
+        "The command must be a list of arguments, the task runs without shell".to_string(),
+      );
+    }
+    // Tasks with a missing dependency are already reported, they should not be unreachable too
+    if task
+      .depends_on()
+      .iter()
+      .all(|prev| states.contains_key(prev))
+    {
+      if task.depends_on().is_empty() {
+        graph.add_start_state(states[id]);
+      }
+    } else {
+      graph.add_start_state(states[id]);
+    }
+    for (_, prev) in config
+      .conditions_outside_dependencies()
+      .iter()
+      .filter(|(t, _)| t == id)
+    {
+      findings.push(
+        &["tasks", id, "if"],
+        Severity::Error,
+        format!(
+          "The condition of {} checks {} which is not in its dependencies",
+          id, prev
+        ),
+      );
+    }
+    for (i, prev) in task.depends_on().iter().enumerate() {
+      match states.get(prev) {
+        Some(prev) => graph.add_arc(*prev, states[id]),
+        None => findings.push(
+          &["tasks", id, "depends_on", &i.to_string()],
+          Severity::Error,
+          format!("{} depends on {} but does not exists", id, prev),
+        ),
+      }
+    }
+  }
+
+  if let Some(cycle) = graph.find_cycle() {
+    findings.push(
+      &["tasks", &cycle[0], "depends_on"],
+      Severity::Error,
+      format!("There is a deadlock in your tasks: {}", cycle.join(" -> ")),
+    );
+  }
+
+  for (state, reachable) in graph.reachable_states().iter().enumerate() {
+    if !reachable {
+      findings.push(
+        &["tasks", ids[state]],
+        Severity::Warning,
+        format!("The task {} is unreachable and will never run", ids[state]),
+      );
+    }
+  }
+}
+
+fn check_paths(config: &Config, findings: &mut Findings) {
+  let mut check_dir = |path: &[&str], dir: &Option<String>| {
+    if let Some(dir) = dir
+      && !Path::new(dir).is_dir()
+    {
+      findings.push(
+        path,
+        Severity::Error,
+        format!("The working directory {} does not exists", dir),
+      );
+    }
+  };
+  check_dir(&["working_dir"], config.working_dir());
+  let mut ids = config.tasks().keys().collect::<Vec<_>>();
+  ids.sort();
+  for id in ids {
+    check_dir(
+      &["tasks", id, "working_dir"],
+      config.tasks()[id].working_dir(),
+    );
+  }
+
+  if let Some(env_file) = config.env_file()
+    && !Path::new(env_file).is_file()
+  {
+    findings.push(
+      &["env_file"],
+      Severity::Error,
+      format!("The env file {} does not exists", env_file),
+    );
+  }
+}
+
+fn check_notification(notification: &super::Notification, findings: &mut Findings) {
+  let notifiers = notification.notifiers();
+  if notifiers.is_empty() {
+    findings.push(
+      &["notification"],
+      Severity::Warning,
+      "The notification has no channel configured".to_string(),
+    );
+  }
+
+  for notifier in notifiers {
+    for (keys, severity, message) in notifier.check() {
+      findings.push(
+        &[&["notification", notifier.name()], keys.as_slice()].concat(),
+        severity,
+        message,
+      );
+    }
+  }
+}
+
+struct Findings<'a> {
+  file: &'a str,
+  locator: &'a Locator,
+  /// The tasks of the included files, with their file, their id in this file and its locator.
+  includes: HashMap<String, (String, String, Rc<Locator>)>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl Findings<'_> {
+  /// Locate the tasks of the included files in their own file.
+  fn locate_includes(&mut self) {
+    let Ok(origins) = include::origins(Path::new(self.file)) else {
+      return;
+    };
+    let mut locators = HashMap::new();
+    for (id, (path, local_id)) in origins {
+      let file = path.display().to_string();
+      if file == self.file {
+        continue;
+      }
+      if !locators.contains_key(&file) {
+        let Ok(yaml) = fs::read_to_string(&path) else {
+          continue;
+        };
+        locators.insert(file.clone(), Rc::new(Locator::new(&yaml)));
+      }
+      let locator = locators[&file].clone();
+      self.includes.insert(id, (file, local_id, locator));
+    }
+  }
+
+  fn push<S: AsRef<str>>(&mut self, path: &[S], severity: Severity, message: String) {
+    let path = path.iter().map(|key| key.as_ref()).collect::<Vec<_>>();
+    if let ["tasks", id, rest @ ..] = path.as_slice()
+      && let Some((file, local_id, locator)) = self.includes.get(*id)
+    {
+      let (line, column) = locator.locate(&[&["tasks", local_id.as_str()], rest].concat());
+      let file = file.clone();
+      self.push_in(file, line, column, severity, message);
+    } else {
+      let (line, column) = self.locator.locate(&path);
+      self.push_at(line, column, severity, message);
+    }
+  }
+
+  fn push_at(&mut self, line: usize, column: usize, severity: Severity, message: String) {
+    self.push_in(self.file.to_string(), line, column, severity, message);
+  }
+
+  fn push_in(
+    &mut self,
+    file: String,
+    line: usize,
+    column: usize,
+    severity: Severity,
+    message: String,
+  ) {
+    self.diagnostics.push(Diagnostic {
+      file,
+      line,
+      column,
+      severity,
+      message,
+    });
+  }
+}
+
+/// Position of the keys and sequence items of a block style YAML document. serde_yaml only gives
+/// the position of syntax and type errors, this is used for the other findings.
+struct Locator {
+  positions: Vec<(Vec<String>, usize, usize)>,
+}
+
+impl Locator {
+  fn new(yaml: &str) -> Locator {
+    let mut positions = vec![];
+    // Open nodes with their indentation and whether they are a sequence item
+    let mut stack: Vec<(usize, bool, Vec<String>)> = vec![];
+    let mut items: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut block_scalar: Option<usize> = None;
+
+    for (n, line) in yaml.lines().enumerate() {
+      let content = line.trim_start();
+      let indent = line.len() - content.len();
+      if content.is_empty() || content.starts_with('#') {
+        continue;
+      }
+      match block_scalar {
+        Some(parent) if indent > parent => continue,
+        _ => block_scalar = None,
+      }
+      if content.starts_with("---") {
+        continue;
+      }
+
+      let (mut indent, mut content) = (indent, content);
+      while let Some(item) = content
+        .strip_prefix("- ")
+        .or((content == "-").then_some(""))
+      {
+        while stack
+          .last()
+          .is_some_and(|(i, is_item, _)| *i > indent || (*i == indent && *is_item))
+        {
+          stack.pop();
+        }
+        let parent = stack.last().map(|(_, _, p)| p.clone()).unwrap_or_default();
+        let index = items.entry(parent.clone()).or_default();
+        let mut path = parent;
+        path.push(index.to_string());
+        *index += 1;
+        positions.push((path.clone(), n + 1, indent + 1));
+        stack.push((indent, true, path));
+        let trimmed = item.trim_start();
+        indent += 2 + item.len() - trimmed.len();
+        content = trimmed;
+      }
+
+      let Some((key, value)) = split_key(content) else {
+        continue;
+      };
+      while stack.last().is_some_and(|(i, _, _)| *i >= indent) {
+        stack.pop();
+      }
+      let mut path = stack.last().map(|(_, _, p)| p.clone()).unwrap_or_default();
+      path.push(key);
+      positions.push((path.clone(), n + 1, indent + 1));
+
+      let value_column = line.len() - value.len();
+      if value.starts_with('|') || value.starts_with('>') {
+        block_scalar = Some(indent);
+      } else if let Some(list) = value.strip_prefix('[') {
+        let mut column = value_column + 1;
+        for (i, elem) in list.trim_end_matches(']').split(',').enumerate() {
+          let offset = elem.len() - elem.trim_start().len();
+          let mut item = path.clone();
+          item.push(i.to_string());
+          positions.push((item, n + 1, column + offset + 1));
+          column += elem.len() + 1;
+        }
+      }
+      stack.push((indent, false, path));
+    }
+
+    Locator { positions }
+  }
+
+  /// Position of the deepest known node of `path`, the beginning of the document otherwise.
+  fn locate<S: AsRef<str>>(&self, path: &[S]) -> (usize, usize) {
+    (0..=path.len())
+      .rev()
+      .find_map(|len| {
+        self
+          .positions
+          .iter()
+          .find(|(p, _, _)| p.len() == len && p.iter().zip(path).all(|(a, b)| a == b.as_ref()))
+      })
+      .map(|(_, line, column)| (*line, *column))
+      .filter(|_| !path.is_empty())
+      .unwrap_or((1, 1))
+  }
+}
+
+/// Split a `key: value` line, the key may be quoted.
+fn split_key(content: &str) -> Option<(String, &str)> {
+  let (key, rest) = if let Some(quote @ ('"' | '\'')) = content.chars().next() {
+    let end = content[1..].find(quote)? + 1;
+    (content[1..end].to_string(), &content[end + 1..])
+  } else {
+    let end = content
+      .match_indices(':')
+      .map(|(i, _)| i)
+      .find(|i| content[i + 1..].is_empty() || content[i + 1..].starts_with(' '))?;
+    if content[..end].contains(" #") {
+      return None;
+    }
+    (content[..end].trim_end().to_string(), &content[end..])
+  };
+  let value = rest.trim_start().strip_prefix(':')?;
+  if !value.is_empty() && !value.starts_with(' ') {
+    return None;
+  }
+  Some((key, value.trim_start()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const INVALID_YAML: &str = "tests/resources/invalid.yml";
+
+  #[test]
+  fn locator() {
+    let locator = Locator::new(
+      "tasks:\n  a:\n    commands:\n      - echo a\n    depends_on: [b, \"c\"]\n  # comment\n  b:\n    commands: |\n      foo: bar\n- x\n",
+    );
+    assert_eq!(locator.locate(&["tasks"]), (1, 1));
+    assert_eq!(locator.locate(&["tasks", "a", "commands", "0"]), (4, 7));
+    assert_eq!(locator.locate(&["tasks", "a", "depends_on", "1"]), (5, 21));
+    assert_eq!(locator.locate(&["tasks", "b"]), (7, 3));
+    assert_eq!(locator.locate(&["tasks", "b", "commands", "foo"]), (8, 5));
+    assert_eq!(locator.locate(&["tasks", "c"]), (1, 1));
+  }
+
+  #[test]
+  fn validate_invalid_yaml() {
+    let yaml = std::fs::read_to_string(INVALID_YAML).unwrap();
+    let diagnostics = validate(INVALID_YAML, &yaml, &HashMap::new())
+      .into_iter()
+      .map(|d| d.to_string())
+      .collect::<Vec<_>>();
+    let file = INVALID_YAML;
+    assert_eq!(
+      diagnostics,
+      vec![
+        format!("{}:4:18: error: a depends on z but does not exists", file),
+        format!("{}:5:3: warning: The task b has no commands", file),
+        format!("{}:6:5: warning: Unknown key `command`", file),
+        format!(
+          "{}:7:3: warning: The task c is unreachable and will never run",
+          file
+        ),
+        format!(
+          "{}:9:5: error: There is a deadlock in your tasks: c -> d -> c",
+          file
+        ),
+        format!(
+          "{}:10:5: error: The working directory /does/not/exists does not exists",
+          file
+        ),
+        format!(
+          "{}:11:3: warning: The task d is unreachable and will never run",
+          file
+        ),
+        format!(
+          "{}:16:5: error: Invalid url `hooks.slack.com`, it must start with http:// or https://",
+          file
+        ),
+        format!("{}:19:5: error: Invalid email address `runtasktic`", file),
+      ]
+    );
+  }
+
+  #[test]
+  fn validate_syntax_error() {
+    let diagnostics = validate(
+      "config.yml",
+      "tasks:\n  a:\n    commands: [echo a\n",
+      &HashMap::new(),
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+    assert_eq!(diagnostics[0].line, 4);
+
+    let diagnostics = validate(
+      "config.yml",
+      "concurrency: abc\ntasks:\n  a:\n    commands: []\n",
+      &HashMap::new(),
+    );
+    assert_eq!(
+      diagnostics[0].to_string().split(": ").next(),
+      Some("config.yml:1:14")
+    );
+  }
+
+  #[test]
+  fn validate_priority() {
+    let diagnostics = validate(
+      "config.yml",
+      "tasks:\n  a:\n    commands: [echo a]\nnotification:\n  ntfy:\n    url: https://ntfy.sh/runtasktic\n    failure_priority: 6\n",
+      &HashMap::new(),
+    );
+    assert_eq!(
+      diagnostics
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>(),
+      vec!["config.yml:7:5: error: Invalid failure priority `6`, it must be between 1 and 5"]
+    );
+  }
+
+  #[test]
+  fn validate_include() {
+    let file = "tests/resources/include_invalid.yml";
+    let yaml = std::fs::read_to_string(file).unwrap();
+    let diagnostics = validate(file, &yaml, &HashMap::new())
+      .into_iter()
+      .map(|d| d.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      diagnostics,
+      vec![
+        "tests/resources/include/invalid.yml:4:18: error: lib.a depends on lib.z but does not exists"
+      ]
+    );
+  }
+
+  #[test]
+  fn validate_condition() {
+    let diagnostics = validate(
+      "config.yml",
+      "tasks:\n  a:\n    commands: [echo a]\n  b:\n    commands: [echo b]\n    if:\n      failed: [a]\n",
+      &HashMap::new(),
+    );
+    assert_eq!(
+      diagnostics
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>(),
+      vec!["config.yml:6:5: error: The condition of b checks a which is not in its dependencies"]
+    );
+    let yaml = std::fs::read_to_string("tests/resources/condition.yml").unwrap();
+    assert_eq!(validate("condition.yml", &yaml, &HashMap::new()), vec![]);
+  }
+
+  #[test]
+  fn validate_vars() {
+    let file = "tests/resources/vars.yml";
+    let yaml = std::fs::read_to_string(file).unwrap();
+    let diagnostics = validate(file, &yaml, &HashMap::new())
+      .into_iter()
+      .map(|d| d.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      diagnostics,
+      vec!["tests/resources/vars.yml:13:7: error: Unresolved variable `target`"]
+    );
+    let vars = HashMap::from([("target".to_string(), "prod".to_string())]);
+    assert_eq!(validate(file, &yaml, &vars), vec![]);
+
+    let diagnostics = validate(
+      "config.yml",
+      "tasks:\n  a:\n    commands: [echo a]\n    if: test -d {{ dir }}\n",
+      &HashMap::new(),
+    );
+    assert_eq!(
+      diagnostics[0].to_string(),
+      "config.yml:4:5: error: Unresolved variable `dir`"
+    );
+  }
+
+  #[test]
+  fn validate_concurrency_yaml() {
+    let yaml = std::fs::read_to_string("tests/resources/concurrency.yml").unwrap();
+    assert_eq!(validate("concurrency.yml", &yaml, &HashMap::new()), vec![]);
+  }
+}
@@ -0,0 +1,65 @@
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const ENV_VAR_PREFIX: &str = "RUNTASKTIK_VAR_";
+
+static TEMPLATE: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap());
+
+/// Variables set with the environment variables `RUNTASKTIK_VAR_<name>`.
+pub fn env_vars() -> HashMap<String, String> {
+  std::env::vars()
+    .filter_map(|(key, value)| Some((key.strip_prefix(ENV_VAR_PREFIX)?.to_string(), value)))
+    .collect()
+}
+
+/// Replace the `{{ var }}` templates of `text`, the names of the missing variables are added to
+/// `unresolved` and their templates are kept.
+pub fn interpolate(
+  text: &str,
+  vars: &HashMap<String, String>,
+  unresolved: &mut Vec<String>,
+) -> String {
+  TEMPLATE
+    .replace_all(text, |caps: &Captures| match vars.get(&caps[1]) {
+      Some(value) => value.clone(),
+      None => {
+        unresolved.push(caps[1].to_string());
+        caps[0].to_string()
+      }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn interpolate() {
+    let vars = HashMap::from([
+      ("version".to_string(), "1.2.0".to_string()),
+      ("dir".to_string(), "/opt".to_string()),
+    ]);
+    let mut unresolved = vec![];
+    assert_eq!(
+      super::interpolate(
+        "cp app-{{version}}.tgz {{ dir }}/{{ dir}}",
+        &vars,
+        &mut unresolved
+      ),
+      "cp app-1.2.0.tgz /opt//opt"
+    );
+    assert!(unresolved.is_empty());
+    assert_eq!(
+      super::interpolate(
+        "echo {{ missing }} ${HOME} {{ version }}",
+        &vars,
+        &mut unresolved
+      ),
+      "echo {{ missing }} ${HOME} 1.2.0"
+    );
+    assert_eq!(unresolved, vec!["missing"]);
+  }
+}
@@ -1,4 +1,6 @@
+use crate::config::parse_matrix_id;
 use crate::fst::TaskFst;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
@@ -6,11 +8,24 @@ use std::process::{Command, Stdio};
 
 pub fn dot_write_file<W: Write>(fst: &TaskFst, writer: &mut W) -> std::io::Result<()> {
   writeln!(writer, "digraph {{")?;
+  // Instances of a matrix are drawn in a cluster named by their task
+  let mut clusters: BTreeMap<String, Vec<String>> = BTreeMap::new();
   let mut iter = fst.iter();
   while iter.has_next() {
     let node = iter.next().unwrap();
-    let label = node.label.replace("\"", "\\\"");
+    let mut label = node.label.replace("\"", "\\\"");
     let id = format_id(&node.label);
+    if let Some((name, values)) = parse_matrix_id(&node.label) {
+      clusters
+        .entry(name.to_string())
+        .or_default()
+        .push(id.clone());
+      let values = values
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>();
+      label = values.join("\\n").replace("\"", "\\\"");
+    }
 
     let shape = if node.next.len() == 0 {
       "doublecircle"
@@ -29,6 +44,14 @@ pub fn dot_write_file<W: Write>(fst: &TaskFst, writer: &mut W) -> std::io::Resul
     }
     iter.mark_done(node.id);
   }
+  for (name, ids) in clusters {
+    writeln!(writer, "  subgraph cluster_{} {{", format_id(&name))?;
+    writeln!(writer, r#"    label="{}""#, name.replace("\"", "\\\""))?;
+    for id in ids {
+      writeln!(writer, "    {}", id)?;
+    }
+    writeln!(writer, "  }}")?;
+  }
   writeln!(writer, "}}")?;
   Ok(())
 }
@@ -55,7 +78,10 @@ pub fn dot_write_png<R: Read, P: AsRef<Path>>(reader: &mut R, path: P) -> std::i
 
 fn format_id(id: &String) -> String {
   id.chars()
-    .map(|c| if c == ' ' { '_' } else { c })
+    .map(|c| match c {
+      ' ' | '[' | '=' | ',' | '.' => '_',
+      c => c,
+    })
     .filter(|c| c.is_alphanumeric() || *c == '_')
     .collect::<String>()
 }
@@ -105,6 +131,39 @@ mod test {
   c -> ds
   ds[label="d%s" shape=doublecircle]
 }
+"#
+    );
+  }
+
+  #[test]
+  fn dot_write_matrix() {
+    let mut fst = TaskFst::new();
+    fst.add_state("build");
+    fst.add_state("deploy[region=eu]");
+    fst.add_state("deploy[region=us]");
+    fst.add_start_state(0);
+    fst.add_arc(0, 1);
+    fst.add_arc(0, 2);
+
+    let mut result: Vec<u8> = vec![];
+    assert!(super::dot_write_file(&fst, &mut result).is_ok());
+    let result = std::str::from_utf8(&result).unwrap();
+    assert_eq!(
+      result,
+      r#"digraph {
+  init_build[label="", shape=point]
+  init_build -> build
+  build[label="build" shape=circle]
+  build -> deploy_region_eu
+  build -> deploy_region_us
+  deploy_region_eu[label="region=eu" shape=doublecircle]
+  deploy_region_us[label="region=us" shape=doublecircle]
+  subgraph cluster_deploy {
+    label="deploy"
+    deploy_region_eu
+    deploy_region_us
+  }
+}
 "#
     );
   }
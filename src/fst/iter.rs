@@ -7,11 +7,14 @@ pub struct TaskIter {
   next: Vec<usize>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
   Todo,
   InProgress,
   Done,
+  Failed,
+  Skipped,
 }
 
 impl TaskIter {
@@ -25,18 +28,44 @@ impl TaskIter {
   }
 
   pub fn is_done(&self) -> bool {
-    self.states.iter().all(|state| *state == TaskStatus::Done)
+    self.states.iter().all(|state| state.is_finished())
   }
 
   pub fn mark_done(&mut self, state: usize) {
-    self.states[state] = TaskStatus::Done;
+    self.mark_finished(state, TaskStatus::Done);
+  }
+
+  /// The task ended with a failure, its dependents will still be executed.
+  pub fn mark_failed(&mut self, state: usize) {
+    self.mark_finished(state, TaskStatus::Failed);
+  }
+
+  /// The task was not executed, its dependents will still be executed unless they are skipped with
+  /// `skip_dependents`.
+  pub fn mark_skipped(&mut self, state: usize) {
+    self.mark_finished(state, TaskStatus::Skipped);
+  }
+
+  /// Mark all the downstream tasks of `state` as skipped, they will never be executed.
+  pub fn skip_dependents(&mut self, state: usize) {
+    for s in self.fst.states[state].next.clone() {
+      if self.states[s] == TaskStatus::Todo {
+        self.states[s] = TaskStatus::Skipped;
+        self.next.retain(|n| *n != s);
+        self.skip_dependents(s);
+      }
+    }
+  }
+
+  fn mark_finished(&mut self, state: usize, status: TaskStatus) {
+    self.states[state] = status;
     for s in &self.fst.states[state].next {
       if self.states[*s] == TaskStatus::Todo // Not done or in progress
         && !self.next.contains(s) // not in the list
-        && self.fst.states[*s] // all previous tasks done
+        && self.fst.states[*s] // all previous tasks finished
           .prev
           .iter()
-          .all(|p| self.states[*p] == TaskStatus::Done)
+          .all(|p| self.states[*p].is_finished())
       {
         self.next.push(*s);
       }
@@ -47,6 +76,41 @@ impl TaskIter {
     self.states[state] = TaskStatus::Done;
   }
 
+  /// Compute again the tasks ready to start, e.g. after restoring finished states with `set_done`.
+  pub fn refresh_next(&mut self) {
+    self.next = (0..self.states.len())
+      .filter(|s| self.states[*s] == TaskStatus::Todo)
+      .filter(|s| {
+        self.fst.states[*s]
+          .prev
+          .iter()
+          .all(|p| self.states[*p].is_finished())
+      })
+      .collect();
+  }
+
+  /// Group the remaining tasks in waves: all the tasks of a wave can run in parallel once the
+  /// previous waves are done. A wave has at most `concurrency` tasks, unless it is negative.
+  pub fn waves(&self, concurrency: i64) -> Vec<Vec<TaskFstState>> {
+    let mut iter = self.clone();
+    let mut waves = vec![];
+    while iter.has_next() {
+      let mut wave = vec![];
+      while iter.has_next() && (concurrency < 0 || (wave.len() as i64) < concurrency.max(1)) {
+        wave.push(iter.next().unwrap());
+      }
+      for state in &wave {
+        iter.mark_done(state.id());
+      }
+      waves.push(wave);
+    }
+    waves
+  }
+
+  pub fn status(&self, state: usize) -> &TaskStatus {
+    &self.states[state]
+  }
+
   pub fn n_in_progress(&self) -> i64 {
     self
       .states
@@ -55,6 +119,14 @@ impl TaskIter {
       .sum()
   }
 
+  pub fn n_skipped(&self) -> i32 {
+    self
+      .states
+      .iter()
+      .filter(|s| **s == TaskStatus::Skipped)
+      .count() as i32
+  }
+
   pub fn has_next(&self) -> bool {
     self.next.len() > 0
   }
@@ -69,3 +141,127 @@ impl TaskIter {
     }
   }
 }
+
+impl TaskStatus {
+  pub fn is_finished(&self) -> bool {
+    matches!(
+      self,
+      TaskStatus::Done | TaskStatus::Failed | TaskStatus::Skipped
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn diamond() -> TaskFst {
+    // a -> b -> d, a -> c -> d, e
+    let mut fst = TaskFst::new();
+    for label in ["a", "b", "c", "d", "e"] {
+      fst.add_state(label);
+    }
+    fst.add_arc(0, 1);
+    fst.add_arc(0, 2);
+    fst.add_arc(1, 3);
+    fst.add_arc(2, 3);
+    fst.add_start_state(0);
+    fst.add_start_state(4);
+    fst
+  }
+
+  #[test]
+  fn mark_failed_continue() {
+    let fst = diamond();
+    let mut iter = fst.iter();
+    assert_eq!(iter.next().map(|s| s.id()), Some(0));
+    assert_eq!(iter.next().map(|s| s.id()), Some(4));
+    iter.mark_failed(0);
+    iter.mark_done(4);
+    assert_eq!(iter.next().map(|s| s.id()), Some(1));
+    assert_eq!(iter.next().map(|s| s.id()), Some(2));
+    iter.mark_done(1);
+    assert!(!iter.has_next());
+    iter.mark_failed(2);
+    assert_eq!(iter.next().map(|s| s.id()), Some(3));
+    iter.mark_done(3);
+    assert!(iter.is_done());
+    assert_eq!(iter.n_skipped(), 0);
+  }
+
+  #[test]
+  fn skip_dependents() {
+    let fst = diamond();
+    let mut iter = fst.iter();
+    assert_eq!(iter.next().map(|s| s.id()), Some(0));
+    iter.mark_done(0);
+    assert_eq!(iter.next().map(|s| s.id()), Some(4));
+    assert_eq!(iter.next().map(|s| s.id()), Some(1));
+    iter.skip_dependents(1);
+    iter.mark_failed(1);
+    assert_eq!(iter.states[3], TaskStatus::Skipped);
+    assert_eq!(iter.next().map(|s| s.id()), Some(2));
+    iter.mark_done(2);
+    assert!(!iter.has_next());
+    assert!(!iter.is_done());
+    iter.mark_done(4);
+    assert!(iter.is_done());
+    assert_eq!(iter.n_skipped(), 1);
+  }
+
+  #[test]
+  fn mark_skipped() {
+    let fst = diamond();
+    let mut iter = fst.iter();
+    assert_eq!(iter.next().map(|s| s.id()), Some(0));
+    iter.mark_skipped(0);
+    assert_eq!(iter.next().map(|s| s.id()), Some(4));
+    assert_eq!(iter.next().map(|s| s.id()), Some(1));
+    iter.skip_dependents(1);
+    iter.mark_skipped(1);
+    assert_eq!(iter.status(3), &TaskStatus::Skipped);
+    assert_eq!(iter.next().map(|s| s.id()), Some(2));
+    iter.mark_done(2);
+    iter.mark_done(4);
+    assert!(iter.is_done());
+    assert_eq!(iter.n_skipped(), 3);
+  }
+
+  #[test]
+  fn waves() {
+    let fst = diamond();
+    let labels = |waves: Vec<Vec<TaskFstState>>| -> Vec<Vec<String>> {
+      waves
+        .iter()
+        .map(|wave| wave.iter().map(|s| s.label().clone()).collect())
+        .collect()
+    };
+    assert_eq!(
+      labels(fst.iter().waves(-1)),
+      vec![vec!["a", "e"], vec!["b", "c"], vec!["d"]]
+    );
+    assert_eq!(
+      labels(fst.iter().waves(1)),
+      vec![vec!["a"], vec!["e"], vec!["b"], vec!["c"], vec!["d"]]
+    );
+
+    let mut iter = fst.iter();
+    iter.set_done(0);
+    iter.refresh_next();
+    assert_eq!(labels(iter.waves(2)), vec![vec!["b", "c"], vec!["e", "d"]]);
+  }
+
+  #[test]
+  fn refresh_next() {
+    let fst = diamond();
+    let mut iter = fst.iter();
+    iter.set_done(0);
+    iter.set_done(2);
+    iter.refresh_next();
+    assert_eq!(iter.next().map(|s| s.id()), Some(1));
+    assert_eq!(iter.next().map(|s| s.id()), Some(4));
+    assert!(!iter.has_next());
+    iter.mark_done(1);
+    assert_eq!(iter.next().map(|s| s.id()), Some(3));
+  }
+}
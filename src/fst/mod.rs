@@ -1,7 +1,11 @@
 pub mod dot;
 mod iter;
+pub mod state;
 
+use crate::config::Config;
+pub use crate::fst::iter::TaskStatus;
 use crate::fst::iter::*;
+use anyhow::{Result, anyhow};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct TaskFst {
@@ -25,6 +29,29 @@ impl TaskFst {
     }
   }
 
+  /// Create the graph of the tasks of a configuration. When `starts` is not empty, these tasks are
+  /// the start states and their dependencies are ignored.
+  pub fn from_config(config: &mut Config, starts: &[String]) -> Result<TaskFst> {
+    let mut graph = TaskFst::new();
+    for task in config.tasks_values_mut() {
+      task.set_state(graph.add_state(task.id()));
+    }
+
+    for task in config.tasks().values() {
+      if (task.depends_on().is_empty() && starts.is_empty()) || starts.contains(task.id()) {
+        graph.add_start_state(task.state());
+      } else {
+        for prev in task.depends_on().iter() {
+          let err_msg = anyhow!("{} depends on {} but does not exists", task.id(), prev);
+          let prev_state = config.tasks().get(prev).ok_or(err_msg)?.state();
+          graph.add_arc(prev_state, task.state());
+        }
+      }
+    }
+
+    Ok(graph)
+  }
+
   pub fn len(&self) -> usize {
     self.states.len()
   }
@@ -52,31 +79,47 @@ impl TaskFst {
     &self.states[id]
   }
 
-  pub fn is_cyclic(&self) -> bool {
-    let visited: &mut Vec<usize> = &mut Vec::with_capacity(self.states.len());
-    for s in &self.start_states {
-      visited.push(*s);
-      if self.is_cyclic_dfs(visited) {
-        return true;
+  /// Labels of the states of a cycle, the first state is repeated at the end: `a -> b -> a`.
+  /// All states are visited, even those not reachable from a start state.
+  pub fn find_cycle(&self) -> Option<Vec<String>> {
+    let visited: &mut Vec<bool> = &mut vec![false; self.states.len()];
+    let path: &mut Vec<usize> = &mut Vec::with_capacity(self.states.len());
+    for s in 0..self.states.len() {
+      if visited[s] {
+        continue;
       }
-      visited.pop();
+      path.push(s);
+      if let Some(start) = self.find_cycle_dfs(path, visited) {
+        let pos = path.iter().position(|p| *p == start).unwrap();
+        let mut cycle = path[pos..]
+          .iter()
+          .map(|p| self.states[*p].label.clone())
+          .collect::<Vec<String>>();
+        cycle.push(self.states[start].label.clone());
+        return Some(cycle);
+      }
+      path.pop();
     }
-    false
+    None
   }
 
-  fn is_cyclic_dfs(&self, visited: &mut Vec<usize>) -> bool {
-    let cur = visited[visited.len() - 1];
+  fn find_cycle_dfs(&self, path: &mut Vec<usize>, visited: &mut Vec<bool>) -> Option<usize> {
+    let cur = path[path.len() - 1];
     for s in &self.states[cur].next {
-      if visited.contains(s) {
-        return true;
+      if path.contains(s) {
+        return Some(*s);
       }
-      visited.push(*s);
-      if self.is_cyclic_dfs(visited) {
-        return true;
+      if visited[*s] {
+        continue;
       }
-      visited.pop();
+      path.push(*s);
+      if let Some(start) = self.find_cycle_dfs(path, visited) {
+        return Some(start);
+      }
+      path.pop();
     }
-    false
+    visited[cur] = true;
+    None
   }
 
   pub fn reachable_states(&self) -> Vec<bool> {
@@ -104,6 +147,28 @@ impl TaskFst {
     }
   }
 
+  /// The states and all their transitive dependencies.
+  pub fn ancestors(&self, states: &[usize]) -> Vec<bool> {
+    self.closure(states, |state| &state.prev)
+  }
+
+  /// The states and all the states depending on them, transitively.
+  pub fn descendants(&self, states: &[usize]) -> Vec<bool> {
+    self.closure(states, |state| &state.next)
+  }
+
+  fn closure<F: Fn(&TaskFstState) -> &Vec<usize>>(&self, states: &[usize], arcs: F) -> Vec<bool> {
+    let mut selected = vec![false; self.states.len()];
+    let mut todo = states.to_vec();
+    while let Some(s) = todo.pop() {
+      if !selected[s] {
+        selected[s] = true;
+        todo.extend(arcs(&self.states[s]));
+      }
+    }
+    selected
+  }
+
   pub fn iter(&self) -> TaskIter {
     TaskIter::new(&self)
   }
@@ -174,33 +239,93 @@ mod test {
     fst.add_start_state(0);
     assert_eq!(fst.reachable_states(), vec![true, true]);
 
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
 
     fst.add_state("c");
 
     assert_eq!(fst.reachable_states(), vec![true, true, false]);
     fst.add_arc(0, 2);
     assert_eq!(fst.reachable_states(), vec![true, true, true]);
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
     fst.add_arc(1, 2);
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
 
     fst.add_state("d");
     assert_eq!(fst.reachable_states(), vec![true, true, true, false]);
     fst.add_arc(2, 3);
     assert_eq!(fst.reachable_states(), vec![true, true, true, true]);
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
 
     fst.add_state("e");
     assert_eq!(fst.reachable_states(), vec![true, true, true, true, false]);
     fst.add_start_state(4);
     assert_eq!(fst.reachable_states(), vec![true, true, true, true, true]);
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
 
     fst.add_arc(4, 3);
-    assert!(!fst.is_cyclic());
+    assert_eq!(fst.find_cycle(), None);
+
+    fst.add_arc(3, 1);
+    assert!(fst.find_cycle().is_some());
+  }
+
+  #[test]
+  pub fn find_cycle() {
+    let mut fst = TaskFst::new();
+    for label in ["a", "b", "c", "d", "e"] {
+      fst.add_state(label);
+    }
+    fst.add_start_state(0);
+    fst.add_arc(0, 1);
+    fst.add_arc(1, 2);
+    fst.add_arc(1, 3);
+    assert_eq!(fst.find_cycle(), None);
 
     fst.add_arc(3, 1);
-    assert!(fst.is_cyclic());
+    assert_eq!(
+      fst.find_cycle(),
+      Some(vec!["b".to_string(), "d".to_string(), "b".to_string()])
+    );
+
+    // Cycle not reachable from any start state
+    let mut fst = TaskFst::new();
+    for label in ["a", "b", "c"] {
+      fst.add_state(label);
+    }
+    fst.add_start_state(0);
+    fst.add_arc(1, 2);
+    fst.add_arc(2, 1);
+    assert_eq!(fst.reachable_states(), vec![true, false, false]);
+    assert_eq!(
+      fst.find_cycle(),
+      Some(vec!["b".to_string(), "c".to_string(), "b".to_string()])
+    );
+
+    let mut fst = TaskFst::new();
+    fst.add_state("a");
+    fst.add_arc(0, 0);
+    assert_eq!(
+      fst.find_cycle(),
+      Some(vec!["a".to_string(), "a".to_string()])
+    );
+  }
+
+  #[test]
+  pub fn ancestors_and_descendants() {
+    // a -> b -> d, a -> c, e -> d
+    let mut fst = TaskFst::new();
+    for label in ["a", "b", "c", "d", "e"] {
+      fst.add_state(label);
+    }
+    fst.add_arc(0, 1);
+    fst.add_arc(0, 2);
+    fst.add_arc(1, 3);
+    fst.add_arc(4, 3);
+
+    assert_eq!(fst.ancestors(&[3]), vec![true, true, false, true, true]);
+    assert_eq!(fst.ancestors(&[2]), vec![true, false, true, false, false]);
+    assert_eq!(fst.ancestors(&[1, 2]), vec![true, true, true, false, false]);
+    assert_eq!(fst.descendants(&[0]), vec![true, true, true, true, false]);
+    assert_eq!(fst.descendants(&[4]), vec![false, false, false, true, true]);
   }
 }
@@ -0,0 +1,109 @@
+use crate::fst::TaskFst;
+use crate::fst::iter::{TaskIter, TaskStatus};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Status of every task of a configuration, saved on disk during a run to be able to resume it.
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RunState {
+  hash: String,
+  states: HashMap<String, TaskStatus>,
+}
+
+impl RunState {
+  /// The state file is located next to the configuration: `/path/.config.yml.state`.
+  pub fn path(config: &Path) -> PathBuf {
+    let name = config
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+      .unwrap_or_default();
+    config.with_file_name(format!(".{}.state", name))
+  }
+
+  /// Hash of the configuration content, the state is invalidated when it changes.
+  pub fn hash(yaml: &str) -> String {
+    sha256::digest(yaml)
+  }
+
+  pub fn new(hash: String, fst: &TaskFst, iter: &TaskIter) -> RunState {
+    let states = (0..fst.len())
+      .map(|id| (fst.states[id].label.clone(), iter.status(id).clone()))
+      .collect();
+    RunState { hash, states }
+  }
+
+  /// Load the state of a previous run. Returns `None` when there is no state file or when it was
+  /// created from another version of the configuration.
+  pub fn load(path: &Path, hash: &str) -> Result<Option<RunState>> {
+    if !path.exists() {
+      return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+      .with_context(|| format!("Can't read the state file {}", path.display()))?;
+    let state: RunState = serde_json::from_str(&content)
+      .with_context(|| format!("Can't process the state file {}", path.display()))?;
+
+    Ok(if state.hash == hash {
+      Some(state)
+    } else {
+      None
+    })
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    let content = serde_json::to_string(self)?;
+    fs::write(path, content)
+      .with_context(|| format!("Can't write the state file {}", path.display()))
+  }
+
+  pub fn status(&self, label: &str) -> Option<&TaskStatus> {
+    self.states.get(label)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn path() {
+    assert_eq!(
+      RunState::path(Path::new("/path/to/config.yml")),
+      PathBuf::from("/path/to/.config.yml.state")
+    );
+    assert_eq!(
+      RunState::path(Path::new("config.yml")),
+      PathBuf::from(".config.yml.state")
+    );
+  }
+
+  #[test]
+  fn save_and_load() -> Result<()> {
+    let mut fst = TaskFst::new();
+    fst.add_state("a");
+    fst.add_state("b");
+    fst.add_arc(0, 1);
+    fst.add_start_state(0);
+    let mut iter = fst.iter();
+    iter.next();
+    iter.mark_done(0);
+    iter.next();
+
+    let path = std::env::temp_dir().join(format!(".runtasktic-{}.state", std::process::id()));
+    let state = RunState::new(RunState::hash("tasks:"), &fst, &iter);
+    state.save(&path)?;
+
+    let loaded = RunState::load(&path, &RunState::hash("tasks:"))?;
+    assert_eq!(loaded, Some(state));
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.status("a"), Some(&TaskStatus::Done));
+    assert_eq!(loaded.status("b"), Some(&TaskStatus::InProgress));
+    assert_eq!(RunState::load(&path, &RunState::hash("tasks: {}"))?, None);
+
+    fs::remove_file(&path)?;
+    assert_eq!(RunState::load(&path, &RunState::hash("tasks:"))?, None);
+    Ok(())
+  }
+}
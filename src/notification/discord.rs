@@ -1,25 +1,26 @@
-use crate::config::Discord;
-use crate::notification::replace_templates;
+use crate::config::{Discord, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json, replace_templates};
+use anyhow::Result;
 
-pub fn post_discord(discord: &Discord, message: &str) -> Result<(), String> {
-  let content = json::object! {
-    "username" => discord.username().as_ref().unwrap_or(&"runtasktic".to_string()).as_str(),
-    "content" => replace_templates(message)
-  };
+#[async_trait::async_trait]
+impl Notifier for Discord {
+  fn name(&self) -> &'static str {
+    "discord"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Discord::when(self)
+  }
 
-  let resp = attohttpc::post(discord.url())
-    .header_append("Content-Type", "application/json")
-    .text(content.dump())
-    .send()
-    .unwrap();
+  fn check(&self) -> Vec<Finding> {
+    check_url("url", self.url()).into_iter().collect()
+  }
 
-  if !resp.status().is_success() {
-    Err(format!(
-      "Notification failed: status code {} and body: {}",
-      resp.status(),
-      resp.text().unwrap_or("<Empty Body>".to_string())
-    ))
-  } else {
-    Ok(())
+  async fn send(&self, event: &Event) -> Result<()> {
+    let content = json::object! {
+      "username" => self.username().as_ref().unwrap_or(&"runtasktic".to_string()).as_str(),
+      "content" => replace_templates(event.message())
+    };
+    post_json(self.url(), content)
   }
 }
@@ -0,0 +1,50 @@
+use crate::config::{GoogleChat, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json};
+use anyhow::Result;
+
+#[async_trait::async_trait]
+impl Notifier for GoogleChat {
+  fn name(&self) -> &'static str {
+    "google_chat"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    GoogleChat::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    check_url("url", self.url()).into_iter().collect()
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    post_json(
+      self.url(),
+      json::object! { "text" => event.message().as_str() },
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(400);
+    let google_chat: GoogleChat = serde_yaml::from_str(&format!("url: {}\n", url))?;
+    let event = Event::end("All tasks ended", vec![]);
+    let err = tokio::runtime::Runtime::new()?
+      .block_on(google_chat.send(&event))
+      .unwrap_err();
+
+    let (method, _, body) = handle.join().unwrap();
+    assert_eq!(method, "POST");
+    assert_eq!(
+      json::parse(&body)?,
+      json::object! { "text" => "All tasks ended" }
+    );
+    assert!(err.to_string().contains("status code 400"));
+    Ok(())
+  }
+}
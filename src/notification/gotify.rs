@@ -0,0 +1,66 @@
+use crate::config::{Gotify, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_priority, check_url, send_json};
+use anyhow::{Result, anyhow};
+
+/// Pushes the message to a Gotify server with an application token, failures use the
+/// `failure_priority`.
+#[async_trait::async_trait]
+impl Notifier for Gotify {
+  fn name(&self) -> &'static str {
+    "gotify"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Gotify::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = check_url("url", self.url()).into_iter().collect();
+    findings.extend(check_priority(
+      0..=10,
+      self.priority(false),
+      self.priority(true),
+    ));
+    findings
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let url = format!("{}/message", self.url().trim_end_matches('/'));
+    let mut content = json::object! {
+      "message" => event.message().as_str(),
+      "priority" => self.priority(event.failed())
+    };
+    if let Some(title) = self.title() {
+      content
+        .insert("title", event.render(title))
+        .map_err(|msg| anyhow!("{}", msg))?;
+    }
+    send_json(
+      attohttpc::post(url).header_append("X-Gotify-Key", self.token()),
+      content,
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let server = url.trim_end_matches("/hook");
+    let gotify: Gotify = serde_yaml::from_str(&format!("url: {}\ntoken: secret\n", server))?;
+    let event = Event::end("All tasks ended", vec![]);
+    tokio::runtime::Runtime::new()?.block_on(gotify.send(&event))?;
+
+    let (_, headers, body) = handle.join().unwrap();
+    assert!(headers.contains(&"x-gotify-key: secret".to_string()));
+    assert_eq!(
+      json::parse(&body)?,
+      json::object! { "message" => "All tasks ended", "priority" => 5 }
+    );
+    Ok(())
+  }
+}
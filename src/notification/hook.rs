@@ -0,0 +1,158 @@
+use crate::config::validate::Severity;
+use crate::config::{CommandArgs, Hook, WhenNotify};
+use crate::notification::{Event, Finding, Notifier};
+use anyhow::{Context, Result, bail};
+use std::io::ErrorKind;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+const ENV_PREFIX: &str = "RUNTASKTIC_";
+
+/// Runs a local command with the message on its stdin and the event in `RUNTASKTIC_*` environment
+/// variables. The command is awaited asynchronously and killed after its timeout.
+#[async_trait::async_trait]
+impl Notifier for Hook {
+  fn name(&self) -> &'static str {
+    "command"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Hook::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    if self.command().to_string().trim().is_empty() {
+      return vec![(
+        vec!["command"],
+        Severity::Error,
+        "The notification command is empty".to_string(),
+      )];
+    }
+    vec![]
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let argv = match self.command() {
+      CommandArgs::Line(line) => vec!["sh".to_string(), "-c".to_string(), line.clone()],
+      CommandArgs::Argv(argv) => argv.clone(),
+    };
+    let Some((program, args)) = argv.split_first() else {
+      bail!("The command is empty");
+    };
+    let mut child = Command::new(program)
+      .args(args)
+      .envs(env(event))
+      .stdin(Stdio::piped())
+      .kill_on_drop(true)
+      .spawn()
+      .with_context(|| format!("Can't run command `{}`", self.command()))?;
+
+    let mut stdin = child.stdin.take().context("Can't open the command stdin")?;
+    let wait = async {
+      match stdin.write_all(event.message().as_bytes()).await {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e),
+        _ => drop(stdin),
+      }
+      child.wait().await
+    };
+    let status = match tokio::time::timeout(self.timeout(), wait).await {
+      Ok(status) => status?,
+      Err(_) => {
+        child.kill().await?;
+        bail!(
+          "The command `{}` timed out after {:?}",
+          self.command(),
+          self.timeout()
+        )
+      }
+    };
+    if !status.success() {
+      bail!("The command `{}` ended with {}", self.command(), status);
+    }
+    Ok(())
+  }
+}
+
+/// The event as environment variables: `RUNTASKTIC_EVENT`, `RUNTASKTIC_FAILED` and one variable
+/// per placeholder, `{task.timed_out}` is `RUNTASKTIC_TIMED_OUT` and `{resume.success}` is
+/// `RUNTASKTIC_RESUME_SUCCESS`. Only the task id keeps its prefix. The status of a task is the
+/// numeric `RUNTASKTIC_STATUS_CODE`, empty when a signal killed it, and `RUNTASKTIC_SIGNAL`.
+fn env(event: &Event) -> Vec<(String, String)> {
+  let name = match event.when() {
+    WhenNotify::TaskEnd => "task_end",
+    _ => "end",
+  };
+  let mut env = vec![
+    (format!("{}EVENT", ENV_PREFIX), name.to_string()),
+    (format!("{}FAILED", ENV_PREFIX), event.failed().to_string()),
+  ];
+  for (key, value) in event.values() {
+    let key = match key.strip_prefix("task.") {
+      Some("id") | None => key.as_str(),
+      Some(key) => key,
+    };
+    let key = key.replace('.', "_").to_uppercase();
+    env.push((format!("{}{}", ENV_PREFIX, key), value.clone()));
+  }
+  if let Some(status) = event.status() {
+    let code = format!("{}STATUS_CODE", ENV_PREFIX);
+    env.retain(|(key, _)| *key != code);
+    let raw = |value: Option<i32>| value.map(|v| v.to_string()).unwrap_or_default();
+    env.push((code, raw(status.code())));
+    env.push((format!("{}SIGNAL", ENV_PREFIX), raw(status.signal())));
+  }
+  env
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::config::{Task, TaskExit, task_end_event};
+  use std::process::ExitStatus;
+  use std::time::Instant;
+
+  fn event(status: ExitStatus) -> Event {
+    let task = Task::new("build", vec!["make".to_string()], vec![], None);
+    let exit = TaskExit::new(status, false, 1);
+    task_end_event("Task {task.id} ended", &task, &exit)
+  }
+
+  #[test]
+  fn send() -> Result<()> {
+    let output = std::env::temp_dir().join(format!("runtasktic-hook-{}.log", std::process::id()));
+    let yaml = format!(
+      "command: 'cat > {0}; echo \" $RUNTASKTIC_EVENT $RUNTASKTIC_TASK_ID $RUNTASKTIC_STATUS_CODE/$RUNTASKTIC_SIGNAL $RUNTASKTIC_FAILED\" >> {0}'\n",
+      output.display()
+    );
+    let hook: Hook = serde_yaml::from_str(&yaml)?;
+    let rt = tokio::runtime::Runtime::new()?;
+
+    rt.block_on(hook.send(&event(ExitStatus::from_raw(2 << 8))))?;
+    let content = std::fs::read_to_string(&output)?;
+    assert_eq!(content, "Task build ended task_end build 2/ true\n");
+
+    rt.block_on(hook.send(&event(ExitStatus::from_raw(libc::SIGKILL))))?;
+    let content = std::fs::read_to_string(&output)?;
+    std::fs::remove_file(&output)?;
+    assert_eq!(content, "Task build ended task_end build /9 true\n");
+    Ok(())
+  }
+
+  #[test]
+  fn timeout() -> Result<()> {
+    let hook: Hook = serde_yaml::from_str("command: [sleep, '10']\ntimeout: 100ms\n")?;
+    let started = Instant::now();
+    let err = tokio::runtime::Runtime::new()?
+      .block_on(hook.send(&event(ExitStatus::from_raw(0))))
+      .unwrap_err();
+
+    assert!(started.elapsed().as_secs() < 5);
+    assert_eq!(
+      err.to_string(),
+      "The command `sleep 10` timed out after 100ms"
+    );
+    Ok(())
+  }
+}
@@ -1,28 +1,87 @@
-use crate::config::Mail;
+use crate::config::validate::Severity;
+use crate::config::{Mail, MailAddress, WhenNotify};
+use crate::notification::{Event, Finding, Notifier};
 use anyhow::{Result, anyhow};
 use mail_send::{Credentials, SmtpClientBuilder, mail_builder::MessageBuilder};
 
-pub async fn notification_email(mail: &Mail, body: &str) -> Result<()> {
-  let from: (String, String) = mail.from().clone().into();
-  let to: Vec<(String, String)> = mail.to().clone().into();
-  let message = MessageBuilder::new()
-    .from(from)
-    .to(to)
-    .subject(mail.subject())
-    .html_body(format!("<p>{}</p>", body))
-    .text_body(body);
+#[async_trait::async_trait]
+impl Notifier for Mail {
+  fn name(&self) -> &'static str {
+    "email"
+  }
 
-  SmtpClientBuilder::new(mail.smtp_hostname(), mail.smtp_port())
-    .implicit_tls(mail.smtp_tls())
-    .credentials(Credentials::Plain {
-      username: mail.smtp_username(),
-      secret: mail.smtp_secret(),
-    })
-    .connect()
-    .await
-    .map_err(|e| anyhow!("Connection to SMTP failed: {}", e))?
-    .send(message)
-    .await
-    .map_err(|e| anyhow!("Failed to sending email: {}", e))?;
-  Ok(())
+  fn when(&self) -> &Option<WhenNotify> {
+    Mail::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let mut findings = vec![];
+    for (key, address) in [("from", self.from()), ("to", self.to())] {
+      let addresses: Vec<(String, String)> = address.clone().into();
+      if addresses.is_empty() {
+        findings.push((
+          vec![key],
+          Severity::Error,
+          format!("The email {} address is missing", key),
+        ));
+      }
+      for (_, address) in addresses {
+        if !is_email(&address) {
+          findings.push((
+            vec![key],
+            Severity::Error,
+            format!("Invalid email address `{}`", address),
+          ));
+        }
+      }
+    }
+    if let MailAddress::V(_) = self.from() {
+      findings.push((
+        vec!["from"],
+        Severity::Warning,
+        "Only the first email address is used as sender".to_string(),
+      ));
+    }
+    if self.smtp_hostname().trim().is_empty() {
+      findings.push((
+        vec!["smtp", "hostname"],
+        Severity::Error,
+        "The smtp hostname is empty".to_string(),
+      ));
+    }
+    findings
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let body = event.message();
+    let from: (String, String) = self.from().clone().into();
+    let to: Vec<(String, String)> = self.to().clone().into();
+    let message = MessageBuilder::new()
+      .from(from)
+      .to(to)
+      .subject(self.subject())
+      .html_body(format!("<p>{}</p>", body))
+      .text_body(body);
+
+    SmtpClientBuilder::new(self.smtp_hostname(), self.smtp_port())
+      .implicit_tls(self.smtp_tls())
+      .credentials(Credentials::Plain {
+        username: self.smtp_username(),
+        secret: self.smtp_secret(),
+      })
+      .connect()
+      .await
+      .map_err(|e| anyhow!("Connection to SMTP failed: {}", e))?
+      .send(message)
+      .await
+      .map_err(|e| anyhow!("Failed to sending email: {}", e))?;
+    Ok(())
+  }
+}
+
+fn is_email(address: &str) -> bool {
+  match address.split_once('@') {
+    Some((user, domain)) => !user.is_empty() && !domain.is_empty() && !domain.contains('@'),
+    None => false,
+  }
 }
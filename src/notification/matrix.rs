@@ -0,0 +1,87 @@
+use crate::config::{Matrix, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, send_json};
+use anyhow::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sends a message in a Matrix room with the client-server API. Failures are sent as `m.text`,
+/// the other messages as `m.notice` which clients usually do not highlight.
+#[async_trait::async_trait]
+impl Notifier for Matrix {
+  fn name(&self) -> &'static str {
+    "matrix"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Matrix::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    check_url("homeserver", self.homeserver())
+      .into_iter()
+      .collect()
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let transaction = format!(
+      "runtasktic-{}-{}",
+      std::process::id(),
+      SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos()
+    );
+    let url = format!(
+      "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+      self.homeserver().trim_end_matches('/'),
+      encode(self.room_id()),
+      transaction
+    );
+    let content = json::object! {
+      "msgtype" => if event.failed() { "m.text" } else { "m.notice" },
+      "body" => event.message().as_str()
+    };
+    send_json(
+      attohttpc::put(url).bearer_auth(self.access_token()),
+      content,
+    )
+  }
+}
+
+/// Percent-encode a path segment, room ids contain `!` and `:`.
+fn encode(segment: &str) -> String {
+  segment
+    .bytes()
+    .map(|b| match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+        (b as char).to_string()
+      }
+      _ => format!("%{:02X}", b),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let homeserver = url.trim_end_matches("/hook");
+    let yaml = format!(
+      "homeserver: {}\nroom_id: '!room:example.com'\naccess_token: secret\n",
+      homeserver
+    );
+    let matrix: Matrix = serde_yaml::from_str(&yaml)?;
+    let event = Event::end("All tasks ended", vec![]);
+    tokio::runtime::Runtime::new()?.block_on(matrix.send(&event))?;
+
+    let (method, headers, body) = handle.join().unwrap();
+    assert_eq!(method, "PUT");
+    assert!(headers.contains(&"authorization: Bearer secret".to_string()));
+    assert_eq!(
+      json::parse(&body)?,
+      json::object! { "msgtype" => "m.notice", "body" => "All tasks ended" }
+    );
+    assert_eq!(encode("!room:example.com"), "%21room%3Aexample.com");
+    Ok(())
+  }
+}
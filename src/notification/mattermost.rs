@@ -0,0 +1,65 @@
+use crate::config::{Mattermost, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json};
+use anyhow::Result;
+
+/// Sends a message to a Mattermost incoming webhook, its payload is compatible with Slack.
+#[async_trait::async_trait]
+impl Notifier for Mattermost {
+  fn name(&self) -> &'static str {
+    "mattermost"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Mattermost::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    check_url("url", self.url()).into_iter().collect()
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let mut content = json::object! {
+      "text" => event.message().as_str()
+    };
+    for (key, value) in [
+      ("channel", self.channel()),
+      ("username", self.username()),
+      ("icon_url", self.icon_url()),
+      ("icon_emoji", self.icon_emoji()),
+    ] {
+      if let Some(value) = value {
+        content.insert(key, value.as_str())?;
+      }
+    }
+    post_json(self.url(), content)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let yaml = format!(
+      "url: {}\nusername: runtasktic\nicon_emoji: ':rocket:'\n",
+      url
+    );
+    let mattermost: Mattermost = serde_yaml::from_str(&yaml)?;
+    let event = Event::end("All tasks ended", vec![]);
+    tokio::runtime::Runtime::new()?.block_on(mattermost.send(&event))?;
+
+    let (_, _, body) = handle.join().unwrap();
+    assert_eq!(
+      json::parse(&body)?,
+      json::object! {
+        "text" => "All tasks ended",
+        "username" => "runtasktic",
+        "icon_emoji" => ":rocket:"
+      }
+    );
+    Ok(())
+  }
+}
@@ -1,15 +1,223 @@
-pub use crate::notification::discord::*;
-pub use crate::notification::mail::*;
-pub use crate::notification::print::*;
-pub use crate::notification::slack::*;
+use crate::config::WhenNotify;
+use crate::config::validate::Severity;
+use anyhow::Result;
 use hostname::get as get_hostname;
 use regex::Regex;
 use std::env::var as get_env;
+use std::ops::RangeInclusive;
+use std::process::ExitStatus;
 
 mod discord;
+mod google_chat;
+mod gotify;
+mod hook;
 mod mail;
+mod matrix;
+mod mattermost;
+mod ntfy;
 mod print;
 mod slack;
+mod teams;
+mod telegram;
+mod webhook;
+
+/// A channel sending the notifications, each backend of the configuration implements it.
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+  /// The name of the channel in the configuration and in the error messages.
+  fn name(&self) -> &'static str;
+  /// The events sent by this channel, all the events accepted by the notification when not set.
+  fn when(&self) -> &Option<WhenNotify>;
+  async fn send(&self, event: &Event) -> Result<()>;
+
+  /// The problems of the channel configuration reported by `validate`, none by default.
+  fn check(&self) -> Vec<Finding> {
+    vec![]
+  }
+
+  fn accepts(&self, event: &Event) -> bool {
+    self
+      .when()
+      .as_ref()
+      .is_none_or(|when| when.should_notify(event.when()))
+  }
+}
+
+/// A problem of a channel configuration, at the keys relative to the channel.
+pub type Finding = (Vec<&'static str>, Severity, String);
+
+/// The end of a task or of all the tasks, with the values of the message placeholders.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Event {
+  when: WhenNotify,
+  message: String,
+  values: Vec<(String, String)>,
+  failed: bool,
+  status: Option<ExitStatus>,
+}
+
+impl Event {
+  /// The event of `when` (`TaskEnd` or `End`), its message is the `template` rendered with the
+  /// `values` of the placeholders.
+  pub fn new(when: WhenNotify, template: &str, values: Vec<(String, String)>) -> Event {
+    let mut event = Event {
+      when,
+      message: String::new(),
+      values,
+      failed: false,
+      status: None,
+    };
+    event.message = event.render(template);
+    event
+  }
+
+  pub fn task_end(template: &str, values: Vec<(String, String)>) -> Event {
+    Event::new(WhenNotify::TaskEnd, template, values)
+  }
+
+  pub fn end(template: &str, values: Vec<(String, String)>) -> Event {
+    Event::new(WhenNotify::End, template, values)
+  }
+
+  /// Mark the event as a failure, a failed task or a run with failures, sent with a higher
+  /// priority by the push notifiers.
+  pub fn with_failure(mut self, failed: bool) -> Event {
+    self.failed = failed;
+    self
+  }
+
+  /// The exit status of the task which ended, for the notifiers needing its raw values.
+  pub fn with_status(mut self, status: ExitStatus) -> Event {
+    self.status = Some(status);
+    self
+  }
+
+  pub fn when(&self) -> &WhenNotify {
+    &self.when
+  }
+  pub fn status(&self) -> Option<ExitStatus> {
+    self.status
+  }
+  pub fn message(&self) -> &String {
+    &self.message
+  }
+  pub fn failed(&self) -> bool {
+    self.failed
+  }
+  /// The values of the placeholders, without braces.
+  pub fn values(&self) -> &Vec<(String, String)> {
+    &self.values
+  }
+  /// Replace the placeholders of the event, `{message}`, `{hostname}` and `{env.*}` in `template`.
+  pub fn render(&self, template: &str) -> String {
+    self
+      .values
+      .iter()
+      .fold(replace_templates(template), |text, (key, value)| {
+        text.replace(&format!("{{{}}}", key), value)
+      })
+      .replace("{message}", &self.message)
+  }
+}
+
+/// Send the event with each notifier accepting it, the errors are printed and do not stop the
+/// other notifiers.
+pub async fn dispatch(notifiers: Vec<&dyn Notifier>, event: &Event) {
+  for notifier in notifiers.into_iter().filter(|n| n.accepts(event)) {
+    if let Err(e) = notifier.send(event).await {
+      eprintln!("Can't use {} notification: {}", notifier.name(), e);
+    }
+  }
+}
+
+fn check_url(key: &'static str, url: &str) -> Option<Finding> {
+  if url.starts_with("https://") || url.starts_with("http://") {
+    return None;
+  }
+  Some((
+    vec![key],
+    Severity::Error,
+    format!(
+      "Invalid url `{}`, it must start with http:// or https://",
+      url
+    ),
+  ))
+}
+
+fn check_priority(range: RangeInclusive<u8>, priority: u8, failure_priority: u8) -> Vec<Finding> {
+  [
+    ("priority", priority),
+    ("failure_priority", failure_priority),
+  ]
+  .into_iter()
+  .filter(|(_, priority)| !range.contains(priority))
+  .map(|(key, priority)| {
+    (
+      vec![key],
+      Severity::Error,
+      format!(
+        "Invalid {} `{}`, it must be between {} and {}",
+        key.replace('_', " "),
+        priority,
+        range.start(),
+        range.end()
+      ),
+    )
+  })
+  .collect()
+}
+
+/// Post a JSON payload to a chat webhook, any status other than 2xx is an error.
+fn post_json(url: &str, content: json::JsonValue) -> Result<()> {
+  send_json(attohttpc::post(url), content)
+}
+
+fn send_json(request: attohttpc::RequestBuilder, content: json::JsonValue) -> Result<()> {
+  check_response(
+    request
+      .header_append("Content-Type", "application/json")
+      .text(content.dump())
+      .send()?,
+  )
+}
+
+fn check_response(resp: attohttpc::Response) -> Result<()> {
+  if !resp.is_success() {
+    anyhow::bail!(
+      "Notification failed: status code {} and body: {}",
+      resp.status(),
+      resp.text().unwrap_or("<Empty Body>".to_string())
+    )
+  }
+  Ok(())
+}
+
+/// A local HTTP server answering `status` to one request, it returns its url and the method,
+/// headers and body of the request.
+#[cfg(test)]
+pub(crate) fn serve(
+  status: u16,
+) -> (
+  String,
+  std::thread::JoinHandle<(String, Vec<String>, String)>,
+) {
+  let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+  let handle = std::thread::spawn(move || {
+    let mut request = server.recv().unwrap();
+    let headers = request
+      .headers()
+      .iter()
+      .map(|h| format!("{}: {}", h.field, h.value))
+      .collect();
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).unwrap();
+    let method = request.method().to_string();
+    request.respond(tiny_http::Response::empty(status)).unwrap();
+    (method, headers, body)
+  });
+  (url, handle)
+}
 
 const HOSTNAME_TEMPLATE: &str = "{hostname}";
 const ENVIRONMENT_TEMPLATE: &str = "\\{env.(?P<key>[a-zA-Z0-9_]+)\\}";
@@ -50,6 +258,55 @@ fn replace_environments<S: AsRef<str>>(message: S) -> String {
 mod test {
   use super::*;
   use std::env::{remove_var as remove_env, set_var as set_env};
+  use std::sync::Mutex;
+
+  struct Recorder {
+    when: Option<WhenNotify>,
+    fail: bool,
+    messages: Mutex<Vec<String>>,
+  }
+
+  #[async_trait::async_trait]
+  impl Notifier for Recorder {
+    fn name(&self) -> &'static str {
+      "recorder"
+    }
+    fn when(&self) -> &Option<WhenNotify> {
+      &self.when
+    }
+    async fn send(&self, event: &Event) -> Result<()> {
+      self.messages.lock().unwrap().push(event.message().clone());
+      anyhow::ensure!(!self.fail, "failed");
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn dispatch() {
+    let recorder = |when: Option<WhenNotify>, fail: bool| Recorder {
+      when,
+      fail,
+      messages: Mutex::new(vec![]),
+    };
+    let all = recorder(None, true);
+    let task_end = recorder(Some(WhenNotify::TaskEnd), false);
+    let end = recorder(Some(WhenNotify::End), false);
+    let never = recorder(Some(WhenNotify::Never), false);
+    let notifiers: Vec<&dyn Notifier> = vec![&all, &task_end, &end, &never];
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(super::dispatch(
+      notifiers.clone(),
+      &Event::task_end("a ended", vec![]),
+    ));
+    rt.block_on(super::dispatch(notifiers, &Event::end("all ended", vec![])));
+
+    let messages = |recorder: &Recorder| recorder.messages.lock().unwrap().clone();
+    assert_eq!(messages(&all), vec!["a ended", "all ended"]);
+    assert_eq!(messages(&task_end), vec!["a ended"]);
+    assert_eq!(messages(&end), vec!["all ended"]);
+    assert!(messages(&never).is_empty());
+  }
 
   #[test]
   fn replace_hostname() {
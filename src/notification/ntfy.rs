@@ -0,0 +1,63 @@
+use crate::config::{Ntfy, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_priority, check_response, check_url};
+use anyhow::Result;
+
+/// Publishes the message on a ntfy topic, failures use the `failure_priority`.
+#[async_trait::async_trait]
+impl Notifier for Ntfy {
+  fn name(&self) -> &'static str {
+    "ntfy"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Ntfy::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = check_url("url", self.url()).into_iter().collect();
+    findings.extend(check_priority(
+      1..=5,
+      self.priority(false),
+      self.priority(true),
+    ));
+    findings
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let mut request = attohttpc::post(self.url())
+      .header_append("Priority", self.priority(event.failed()).to_string());
+    if let Some(title) = self.title() {
+      request = request.try_header_append("Title", event.render(title))?;
+    }
+    if let Some(token) = self.token() {
+      request = request.bearer_auth(token);
+    }
+    check_response(request.text(event.message()).send()?)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let yaml = format!("url: {}\ntitle: 'Task {{task.id}}'\npriority: 2\n", url);
+    let ntfy: Ntfy = serde_yaml::from_str(&yaml)?;
+    let event = Event::task_end(
+      "Task {task.id} failed",
+      vec![("task.id".to_string(), "build".to_string())],
+    )
+    .with_failure(true);
+    tokio::runtime::Runtime::new()?.block_on(ntfy.send(&event))?;
+
+    let (_, headers, body) = handle.join().unwrap();
+    assert!(headers.contains(&"priority: 5".to_string()));
+    assert!(headers.contains(&"title: Task build".to_string()));
+    assert_eq!(body, "Task build failed");
+    assert_eq!(ntfy.priority(false), 2);
+    Ok(())
+  }
+}
@@ -1,19 +1,51 @@
-use crate::config::Print;
+use crate::config::validate::Severity;
+use crate::config::{Print, WhenNotify};
+use crate::notification::{Event, Finding, Notifier};
+use anyhow::{Context, Result};
 use chrono::Local;
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::Path;
 
-pub fn notification_print(print: &Print, message: &str) -> Result<(), String> {
-  writeln(
-    print.output(),
-    format!(
-      "{} INFO [notification::print] {}",
-      Local::now().format("%Y-%m-%d %H:%M:%S,%3f"),
-      message
-    ),
-  )
-  .map_err(|msg| format!("Can't open output file {}: {}", print.output(), msg))?;
-  Ok(())
+#[async_trait::async_trait]
+impl Notifier for Print {
+  fn name(&self) -> &'static str {
+    "print"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Print::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let output = self.output();
+    let parent = Path::new(output).parent();
+    if !["stdout", "stderr"].contains(&output.as_str())
+      && parent.is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir())
+    {
+      return vec![(
+        vec!["output"],
+        Severity::Error,
+        format!(
+          "The directory of the print output {} does not exists",
+          output
+        ),
+      )];
+    }
+    vec![]
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    writeln(
+      self.output(),
+      format!(
+        "{} INFO [notification::print] {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S,%3f"),
+        event.message()
+      ),
+    )
+    .with_context(|| format!("Can't open output file {}", self.output()))
+  }
 }
 
 fn writeln(output: &String, message: String) -> std::io::Result<()> {
@@ -27,3 +59,32 @@ fn writeln(output: &String, message: String) -> std::io::Result<()> {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn send() -> Result<()> {
+    let output = std::env::temp_dir().join(format!("runtasktic-print-{}.log", std::process::id()));
+    let print: Print = serde_yaml::from_str(&format!("output: {}\nwhen: end\n", output.display()))?;
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(crate::notification::dispatch(
+      vec![&print],
+      &Event::task_end(
+        "task {task.id} ended",
+        vec![("task.id".to_string(), "a".to_string())],
+      ),
+    ));
+    rt.block_on(crate::notification::dispatch(
+      vec![&print],
+      &Event::end("all tasks ended", vec![]),
+    ));
+
+    let content = std::fs::read_to_string(&output)?;
+    std::fs::remove_file(&output)?;
+    assert!(!content.contains("task a ended"));
+    assert!(content.ends_with("INFO [notification::print] all tasks ended\n"));
+    Ok(())
+  }
+}
@@ -1,32 +1,43 @@
-use crate::config::Slack;
-use crate::notification::replace_templates;
+use crate::config::validate::Severity;
+use crate::config::{Slack, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json, replace_templates};
+use anyhow::{Result, anyhow};
 
-pub fn post_slack(slack: &Slack, message: &str) -> Result<(), String> {
-  let mut content = json::object! {
-    "channel" => slack.channel().as_str(),
-    "username" => slack.username().as_ref().unwrap_or(&"runtasktic".to_string()).as_str(),
-    "text" => replace_templates(message)
-  };
+#[async_trait::async_trait]
+impl Notifier for Slack {
+  fn name(&self) -> &'static str {
+    "slack"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Slack::when(self)
+  }
 
-  if let Some(emoji) = slack.emoji() {
-    content
-      .insert("icon_emoji", emoji.as_str())
-      .map_err(|msg| format!("{}", msg))?;
+  fn check(&self) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = check_url("url", self.url()).into_iter().collect();
+    if self.channel().trim().is_empty() {
+      findings.push((
+        vec!["channel"],
+        Severity::Error,
+        "The slack channel is empty".to_string(),
+      ));
+    }
+    findings
   }
 
-  let resp = attohttpc::post(slack.url())
-    .header_append("Content-Type", "application/json")
-    .text(content.dump())
-    .send()
-    .unwrap();
+  async fn send(&self, event: &Event) -> Result<()> {
+    let mut content = json::object! {
+      "channel" => self.channel().as_str(),
+      "username" => self.username().as_ref().unwrap_or(&"runtasktic".to_string()).as_str(),
+      "text" => replace_templates(event.message())
+    };
+
+    if let Some(emoji) = self.emoji() {
+      content
+        .insert("icon_emoji", emoji.as_str())
+        .map_err(|msg| anyhow!("{}", msg))?;
+    }
 
-  if resp.status() != 200 {
-    Err(format!(
-      "Notification failed: status code {} and body: {}",
-      resp.status(),
-      resp.text().unwrap_or("<Empty Body>".to_string())
-    ))
-  } else {
-    Ok(())
+    post_json(self.url(), content)
   }
 }
@@ -0,0 +1,81 @@
+use crate::config::{Teams, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json};
+use anyhow::Result;
+
+/// Sends an Adaptive Card to a Teams incoming webhook.
+#[async_trait::async_trait]
+impl Notifier for Teams {
+  fn name(&self) -> &'static str {
+    "teams"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Teams::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    check_url("url", self.url()).into_iter().collect()
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let mut body = json::array![];
+    if let Some(title) = self.title() {
+      body.push(json::object! {
+        "type" => "TextBlock",
+        "text" => event.render(title),
+        "weight" => "Bolder",
+        "size" => "Medium",
+        "wrap" => true
+      })?;
+    }
+    body.push(json::object! {
+      "type" => "TextBlock",
+      "text" => event.message().as_str(),
+      "wrap" => true
+    })?;
+
+    let mut card = json::object! {
+      "$schema" => "http://adaptivecards.io/schemas/adaptive-card.json",
+      "type" => "AdaptiveCard",
+      "version" => "1.4"
+    };
+    card.insert("body", body)?;
+
+    let mut attachment = json::object! {
+      "contentType" => "application/vnd.microsoft.card.adaptive"
+    };
+    attachment.insert("content", card)?;
+
+    let mut content = json::object! { "type" => "message", "attachments" => json::array![] };
+    content["attachments"].push(attachment)?;
+    post_json(self.url(), content)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let teams: Teams = serde_yaml::from_str(&format!("url: {}\ntitle: \"{{task.id}}\"\n", url))?;
+    let event = Event::task_end(
+      "Task {task.id} ended",
+      vec![("task.id".to_string(), "build".to_string())],
+    );
+    tokio::runtime::Runtime::new()?.block_on(teams.send(&event))?;
+
+    let (_, _, body) = handle.join().unwrap();
+    let body = json::parse(&body)?;
+    let card = &body["attachments"][0];
+    assert_eq!(
+      card["contentType"],
+      "application/vnd.microsoft.card.adaptive"
+    );
+    assert_eq!(card["content"]["body"][0]["text"], "build");
+    assert_eq!(card["content"]["body"][1]["text"], "Task build ended");
+    Ok(())
+  }
+}
@@ -0,0 +1,73 @@
+use crate::config::validate::Severity;
+use crate::config::{Telegram, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_url, post_json};
+use anyhow::Result;
+
+/// Sends a message with the Telegram Bot API, only the failures ring the phone, the other messages
+/// are sent silently.
+#[async_trait::async_trait]
+impl Notifier for Telegram {
+  fn name(&self) -> &'static str {
+    "telegram"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Telegram::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = check_url("api_url", self.api_url()).into_iter().collect();
+    if self.chat_id().trim().is_empty() {
+      findings.push((
+        vec!["chat_id"],
+        Severity::Error,
+        "The telegram chat id is empty".to_string(),
+      ));
+    }
+    findings
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let url = format!(
+      "{}/bot{}/sendMessage",
+      self.api_url().trim_end_matches('/'),
+      self.bot_token()
+    );
+    let content = json::object! {
+      "chat_id" => self.chat_id().as_str(),
+      "text" => event.message().as_str(),
+      "disable_notification" => !event.failed()
+    };
+    post_json(&url, content)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  #[test]
+  fn send() -> Result<()> {
+    let (url, handle) = serve(200);
+    let api_url = url.trim_end_matches("/hook");
+    let yaml = format!(
+      "api_url: {}\nbot_token: '123:abc'\nchat_id: '42'\n",
+      api_url
+    );
+    let telegram: Telegram = serde_yaml::from_str(&yaml)?;
+    let event = Event::end("All tasks ended", vec![]).with_failure(true);
+    tokio::runtime::Runtime::new()?.block_on(telegram.send(&event))?;
+
+    let (_, _, body) = handle.join().unwrap();
+    assert_eq!(
+      json::parse(&body)?,
+      json::object! {
+        "chat_id" => "42",
+        "text" => "All tasks ended",
+        "disable_notification" => false
+      }
+    );
+    Ok(())
+  }
+}
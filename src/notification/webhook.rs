@@ -0,0 +1,179 @@
+use crate::config::validate::Severity;
+use crate::config::{Webhook, WebhookAuth, WebhookFormat, WhenNotify};
+use crate::notification::{Event, Finding, Notifier, check_response, check_url};
+use anyhow::{Context, Result, bail};
+use attohttpc::Method;
+use attohttpc::header::HeaderName;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[async_trait::async_trait]
+impl Notifier for Webhook {
+  fn name(&self) -> &'static str {
+    "webhook"
+  }
+
+  fn when(&self) -> &Option<WhenNotify> {
+    Webhook::when(self)
+  }
+
+  fn check(&self) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = check_url("url", self.url()).into_iter().collect();
+    if Method::from_bytes(self.method().to_uppercase().as_bytes()).is_err() {
+      findings.push((
+        vec!["method"],
+        Severity::Error,
+        format!("Invalid method `{}`", self.method()),
+      ));
+    }
+    if self.format() == &WebhookFormat::Form
+      && !self
+        .body()
+        .as_object()
+        .is_some_and(|fields| fields.values().all(|value| !is_nested(value)))
+    {
+      findings.push((
+        vec!["body"],
+        Severity::Error,
+        "The body of a form must be fields without nested values".to_string(),
+      ));
+    }
+    findings
+  }
+
+  async fn send(&self, event: &Event) -> Result<()> {
+    let method = Method::from_bytes(self.method().to_uppercase().as_bytes())
+      .with_context(|| format!("Invalid method `{}`", self.method()))?;
+    let mut request = attohttpc::RequestBuilder::new(method, self.url());
+    for (name, value) in self.headers() {
+      request = request.try_header_append(HeaderName::try_from(name)?, event.render(value))?;
+    }
+    request = match self.auth() {
+      Some(WebhookAuth::Bearer { token_env }) => request.bearer_auth(secret(token_env)?),
+      Some(WebhookAuth::Basic {
+        username,
+        password_env,
+      }) => request.basic_auth(username, Some(secret(password_env)?)),
+      None => request,
+    };
+
+    let body = render(self.body(), event);
+    let resp = match self.format() {
+      WebhookFormat::Json => request.json(&body)?.send()?,
+      WebhookFormat::Form => request.form(&form(&body)?)?.send()?,
+    };
+
+    check_response(resp)
+  }
+}
+
+/// Render the placeholders in the strings of the template, the other values are kept.
+fn render(template: &Value, event: &Event) -> Value {
+  match template {
+    Value::String(text) => Value::String(event.render(text)),
+    Value::Array(values) => Value::Array(values.iter().map(|v| render(v, event)).collect()),
+    Value::Object(fields) => Value::Object(
+      fields
+        .iter()
+        .map(|(key, value)| (key.clone(), render(value, event)))
+        .collect(),
+    ),
+    value => value.clone(),
+  }
+}
+
+/// The fields of a form body, numbers and booleans are written as in JSON.
+fn form(body: &Value) -> Result<BTreeMap<&String, String>> {
+  let Some(fields) = body.as_object() else {
+    bail!("The body of a form must be fields");
+  };
+  fields
+    .iter()
+    .map(|(key, value)| match value {
+      Value::String(text) => Ok((key, text.clone())),
+      value if is_nested(value) => bail!("The field `{}` of the form has nested values", key),
+      value => Ok((key, value.to_string())),
+    })
+    .collect()
+}
+
+fn is_nested(value: &Value) -> bool {
+  value.is_array() || value.is_object()
+}
+
+fn secret(env: &str) -> Result<String> {
+  std::env::var(env).with_context(|| format!("The environment variable {} is not set", env))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::notification::serve;
+
+  fn event() -> Event {
+    Event::task_end(
+      "Task {task.id} ended",
+      vec![("task.id".to_string(), "build".to_string())],
+    )
+  }
+
+  #[test]
+  fn send_json() -> Result<()> {
+    let (url, handle) = serve(200);
+    unsafe { std::env::set_var("RUNTASKTIK_TEST_WEBHOOK_TOKEN", "secret") };
+    let yaml = format!(
+      "url: {}\nheaders:\n  X-Task: \"{{task.id}}\"\nauth:\n  token_env: RUNTASKTIK_TEST_WEBHOOK_TOKEN\nbody:\n  text: \"{{message}}\"\n  alert:\n    task: \"{{task.id}}\"\n    tags: [ ci, \"{{task.id}}\" ]\n    priority: 2\n    open: true\n    owner: null\n",
+      url
+    );
+    let webhook: Webhook = serde_yaml::from_str(&yaml)?;
+    tokio::runtime::Runtime::new()?.block_on(webhook.send(&event()))?;
+
+    let (method, headers, body) = handle.join().unwrap();
+    assert_eq!(method, "POST");
+    assert!(headers.contains(&"x-task: build".to_string()));
+    assert!(headers.contains(&"authorization: Bearer secret".to_string()));
+    let body: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(
+      body,
+      serde_json::json!({
+        "text": "Task build ended",
+        "alert": {
+          "task": "build",
+          "tags": ["ci", "build"],
+          "priority": 2,
+          "open": true,
+          "owner": null
+        }
+      })
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn send_form() -> Result<()> {
+    let (url, handle) = serve(500);
+    let yaml = format!("url: {}\nmethod: put\nformat: form\n", url);
+    let webhook: Webhook = serde_yaml::from_str(&yaml)?;
+    let err = tokio::runtime::Runtime::new()?
+      .block_on(webhook.send(&event()))
+      .unwrap_err();
+
+    let (method, _, body) = handle.join().unwrap();
+    assert_eq!(method, "PUT");
+    assert_eq!(body, "message=Task+build+ended");
+    assert!(err.to_string().contains("status code 500"));
+    assert!(webhook.check().is_empty());
+
+    let yaml = "url: http://localhost\nformat: form\nbody:\n  count: 2\n  tags: [ ci ]\n";
+    let webhook: Webhook = serde_yaml::from_str(yaml)?;
+    assert_eq!(
+      webhook.check(),
+      vec![(
+        vec!["body"],
+        Severity::Error,
+        "The body of a form must be fields without nested values".to_string()
+      )]
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,171 @@
+use crate::utils::reaper::{Event, Reaper};
+use libc::{ESRCH, SIGKILL, SIGTERM, getpgid, kill};
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::time::{Duration, Instant};
+
+/// Bound the runtime of a child process: SIGTERM once the timeout is reached, then SIGKILL when
+/// the grace period is over. The signals are sent to the process group of the child when it leads
+/// its own group, to the child only otherwise.
+#[derive(Debug)]
+pub struct Deadline {
+  timeout: Option<Duration>,
+  grace_period: Duration,
+  started: Instant,
+  terminated: Option<Instant>,
+  killed: bool,
+  interrupts: u32,
+}
+
+impl Deadline {
+  pub fn new(timeout: Option<Duration>, grace_period: Duration) -> Deadline {
+    Deadline {
+      timeout,
+      grace_period,
+      started: Instant::now(),
+      terminated: None,
+      killed: false,
+      interrupts: 0,
+    }
+  }
+
+  pub fn timed_out(&self) -> bool {
+    self.terminated.is_some()
+  }
+
+  pub fn interrupted(&self) -> bool {
+    self.interrupts > 0
+  }
+
+  /// Forward an interruption of runtasktic to the child `pid`: SIGTERM the first time, SIGKILL next.
+  pub fn interrupt(&mut self, pid: u32) -> io::Result<()> {
+    self.interrupts += 1;
+    if self.interrupts == 1 {
+      signal(pid, SIGTERM)
+    } else {
+      self.killed = true;
+      signal(pid, SIGKILL)
+    }
+  }
+
+  /// When the next signal is due, `None` when there is nothing left to send.
+  pub fn next_check(&self) -> Option<Instant> {
+    match (self.timeout, self.terminated) {
+      (Some(timeout), None) => Some(self.started + timeout),
+      (Some(_), Some(terminated)) if !self.killed => Some(terminated + self.grace_period),
+      _ => None,
+    }
+  }
+
+  /// Send the signal due to the child `pid`, if any. Must be called when `next_check` is reached.
+  pub fn check(&mut self, pid: u32) -> io::Result<()> {
+    let Some(timeout) = self.timeout else {
+      return Ok(());
+    };
+
+    match self.terminated {
+      None if self.started.elapsed() >= timeout => {
+        self.terminated = Some(Instant::now());
+        signal(pid, SIGTERM)
+      }
+      Some(terminated) if !self.killed && terminated.elapsed() >= self.grace_period => {
+        self.killed = true;
+        signal(pid, SIGKILL)
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Wait for the end of the child, enforcing the timeout and forwarding SIGINT and SIGTERM.
+  pub fn wait(&mut self, child: Child) -> io::Result<ExitStatus> {
+    let pid = child.id();
+    let reaper = Reaper::new();
+    let _signals = reaper.catch_stop_signals()?;
+    reaper.watch((), child);
+    loop {
+      match reaper.next(self.next_check()) {
+        Some(Event::Exit(_, status)) => return status,
+        Some(Event::Signal(_)) => self.interrupt(pid)?,
+        None => self.check(pid)?,
+      }
+    }
+  }
+}
+
+fn signal(pid: u32, signal: i32) -> io::Result<()> {
+  // No process was spawned, 0 would signal the group of runtasktic
+  if pid == 0 {
+    return Ok(());
+  }
+  let pid = pid as i32;
+  // A negative pid sends the signal to the whole process group
+  let target = if unsafe { getpgid(pid) } == pid {
+    -pid
+  } else {
+    pid
+  };
+  if unsafe { kill(target, signal) } == 0 {
+    return Ok(());
+  }
+  match io::Error::last_os_error() {
+    // The group is already gone, its exit will be received soon
+    e if e.raw_os_error() == Some(ESRCH) => Ok(()),
+    e => Err(e),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::os::unix::process::CommandExt;
+  use std::process::Command;
+
+  #[test]
+  fn interrupt() -> io::Result<()> {
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg("trap '' TERM; sleep 30")
+      .process_group(0)
+      .spawn()?;
+    let mut deadline = Deadline::new(None, Duration::from_secs(10));
+    let started = Instant::now();
+    // Let the shell install its trap
+    std::thread::sleep(Duration::from_millis(300));
+
+    deadline.interrupt(child.id())?;
+    assert!(deadline.interrupted());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(child.try_wait()?.is_none());
+    deadline.interrupt(child.id())?;
+    assert!(!child.wait()?.success());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    Ok(())
+  }
+
+  #[test]
+  fn interrupt_without_group() -> io::Result<()> {
+    // In the group of the tests, only the child must be signaled
+    let mut child = Command::new("sleep").arg("30").spawn()?;
+    let mut deadline = Deadline::new(None, Duration::from_secs(10));
+
+    deadline.interrupt(child.id())?;
+    assert!(!child.wait()?.success());
+    Ok(())
+  }
+
+  #[test]
+  fn wait() -> io::Result<()> {
+    let child = Command::new("sh")
+      .arg("-c")
+      .arg("sleep 30")
+      .process_group(0)
+      .spawn()?;
+    let mut deadline = Deadline::new(Some(Duration::from_millis(100)), Duration::from_secs(10));
+    let started = Instant::now();
+
+    assert!(!deadline.wait(child)?.success());
+    assert!(deadline.timed_out());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    Ok(())
+  }
+}
@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+const TASK_ID_TEMPLATE: &str = "{task.id}";
+const DATE_TEMPLATE: &str = "{date}";
+const TIME_TEMPLATE: &str = "{time}";
+
+/// Resolve the templates of an output path for a task: `{task.id}`, `{date}`, `{time}`,
+/// `{hostname}` and `{env.*}`.
+pub fn resolve_output(output: &Option<String>, id: &str, now: DateTime<Local>) -> Option<String> {
+  output.as_ref().map(|output| {
+    crate::notification::replace_templates(output)
+      .replace(TASK_ID_TEMPLATE, id)
+      .replace(DATE_TEMPLATE, &now.format("%Y-%m-%d").to_string())
+      .replace(TIME_TEMPLATE, &now.format("%H-%M-%S").to_string())
+  })
+}
+
+/// Remove the oldest log files created from the output template of a task, the `keep_last - 1`
+/// most recent are kept to let the next run create its own.
+/// Only templates with `{date}` or `{time}` in their file name create one file per run.
+pub fn prune_outputs(output: &Option<String>, id: &str, keep_last: usize) -> Result<()> {
+  let Some(output) = output else {
+    return Ok(());
+  };
+  let path = Path::new(output);
+  let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+    return Ok(());
+  };
+  let name = name.to_string_lossy();
+  if !name.contains(DATE_TEMPLATE) && !name.contains(TIME_TEMPLATE) {
+    return Ok(());
+  }
+  let dir =
+    crate::notification::replace_templates(dir.to_string_lossy()).replace(TASK_ID_TEMPLATE, id);
+  let dir = if dir.is_empty() { ".".to_string() } else { dir };
+  if !Path::new(&dir).is_dir() {
+    return Ok(());
+  }
+  let regex = output_regex(&name, id)?;
+
+  let mut files = fs::read_dir(&dir)
+    .with_context(|| format!("Can't read the log directory {}", dir))?
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| regex.is_match(&entry.file_name().to_string_lossy()))
+    .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+    .collect::<Vec<_>>();
+  files.sort();
+
+  let remove = files.len().saturating_sub(keep_last.saturating_sub(1));
+  for (_, file) in files.into_iter().take(remove) {
+    fs::remove_file(&file)
+      .with_context(|| format!("Can't remove the old log file {}", file.display()))?;
+  }
+  Ok(())
+}
+
+fn output_regex(name: &str, id: &str) -> Result<Regex> {
+  let name = regex::escape(&crate::notification::replace_templates(name));
+  let pattern = name
+    .replace(&regex::escape(TASK_ID_TEMPLATE), &regex::escape(id))
+    .replace(&regex::escape(DATE_TEMPLATE), r"\d{4}-\d{2}-\d{2}")
+    .replace(&regex::escape(TIME_TEMPLATE), r"\d{2}-\d{2}-\d{2}");
+  Ok(Regex::new(&format!("^{}$", pattern))?)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use chrono::TimeZone;
+
+  #[test]
+  fn resolve_output() {
+    let now = Local.with_ymd_and_hms(2024, 1, 31, 8, 5, 9).unwrap();
+    assert_eq!(
+      super::resolve_output(
+        &Some("logs/{task.id}-{date}_{time}.out".to_string()),
+        "a",
+        now
+      ),
+      Some("logs/a-2024-01-31_08-05-09.out".to_string())
+    );
+    assert_eq!(
+      super::resolve_output(&Some("none".to_string()), "a", now),
+      Some("none".to_string())
+    );
+    assert_eq!(super::resolve_output(&None, "a", now), None);
+  }
+
+  #[test]
+  fn prune_outputs() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("runtasktic-logs-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    for name in [
+      "a-2024-01-01.out",
+      "a-2024-01-02.out",
+      "a-2024-01-03.out",
+      "b-2024-01-01.out",
+      "a-latest.out",
+    ] {
+      fs::write(dir.join(name), name)?;
+      std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let template = Some(format!("{}/{{task.id}}-{{date}}.out", dir.display()));
+
+    super::prune_outputs(&template, "a", 2)?;
+    let mut files = fs::read_dir(&dir)?
+      .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+      .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+      files,
+      vec!["a-2024-01-03.out", "a-latest.out", "b-2024-01-01.out"]
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
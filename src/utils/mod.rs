@@ -1 +1,7 @@
+pub mod deadline;
+pub mod logs;
+pub mod output;
+pub mod reaper;
+pub mod signals;
+pub mod terminal;
 pub mod traits;
@@ -0,0 +1,108 @@
+use chrono::Local;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::thread::JoinHandle;
+
+const COLOURS: [&str; 10] = ["36", "33", "32", "35", "34", "96", "93", "92", "95", "94"];
+const RESET: &str = "\x1b[0m";
+
+/// Prefix each line of a task output with its id, like docker-compose does.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Prefix {
+  label: String,
+  colour: Option<&'static str>,
+  timestamp: bool,
+}
+
+impl Prefix {
+  /// `width` is the length of the longest task id, to align all outputs.
+  pub fn new(id: &str, width: usize, timestamp: bool) -> Prefix {
+    let colour = if std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+      Some(colour(id))
+    } else {
+      None
+    };
+    Prefix {
+      label: format!("{:<width$}", id, width = width),
+      colour,
+      timestamp,
+    }
+  }
+
+  /// Copy the reader line by line in a new thread, each line is written at once on stdout or stderr.
+  pub fn print<R: Read + Send + 'static>(self, reader: R, stderr: bool) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+      let mut reader = BufReader::new(reader);
+      let mut line = vec![];
+      while let Ok(n) = reader.read_until(b'\n', &mut line) {
+        if n == 0 {
+          break;
+        }
+        let buf = self.format(&line);
+        let _ = if stderr {
+          std::io::stderr().lock().write_all(&buf)
+        } else {
+          std::io::stdout().lock().write_all(&buf)
+        };
+        line.clear();
+      }
+    })
+  }
+
+  fn format(&self, line: &[u8]) -> Vec<u8> {
+    let mut buf = vec![];
+    if self.timestamp {
+      let _ = write!(buf, "{} ", Local::now().format("%Y-%m-%d %H:%M:%S,%3f"));
+    }
+    let _ = match self.colour {
+      Some(colour) => write!(buf, "\x1b[{}m{} |{} ", colour, self.label, RESET),
+      None => write!(buf, "{} | ", self.label),
+    };
+    buf.extend_from_slice(line);
+    if !line.ends_with(b"\n") {
+      buf.push(b'\n');
+    }
+    buf
+  }
+}
+
+/// The colour of a task is computed from its id, so it is the same between runs.
+fn colour(id: &str) -> &'static str {
+  let hash = id.bytes().fold(0x811c9dc5u32, |hash, b| {
+    (hash ^ b as u32).wrapping_mul(0x01000193)
+  });
+  COLOURS[hash as usize % COLOURS.len()]
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn format() {
+    let prefix = Prefix {
+      label: "a    ".to_string(),
+      colour: None,
+      timestamp: false,
+    };
+    assert_eq!(prefix.format(b"Begin a\n"), b"a     | Begin a\n".to_vec());
+    assert_eq!(
+      prefix.format(b"no newline"),
+      b"a     | no newline\n".to_vec()
+    );
+
+    let prefix = Prefix {
+      colour: Some("36"),
+      ..prefix
+    };
+    assert_eq!(
+      prefix.format(b"End a\n"),
+      b"\x1b[36ma     |\x1b[0m End a\n".to_vec()
+    );
+  }
+
+  #[test]
+  fn colour() {
+    assert_eq!(super::colour("task"), super::colour("task"));
+    assert_eq!(Prefix::new("a", 5, false).label, "a    ".to_string());
+  }
+}
@@ -0,0 +1,97 @@
+use crate::utils::signals::StopSignals;
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::thread;
+use std::time::Instant;
+
+/// What woke the reaper up.
+#[derive(Debug)]
+pub enum Event<K> {
+  /// A watched child ended.
+  Exit(K, io::Result<ExitStatus>),
+  /// SIGINT or SIGTERM was received.
+  Signal(i32),
+}
+
+/// Wait for the end of children in background threads, each exit is received as soon as it happens
+/// instead of polling the children.
+pub struct Reaper<K> {
+  sender: Sender<Event<K>>,
+  receiver: Receiver<Event<K>>,
+}
+
+impl<K: Send + 'static> Reaper<K> {
+  pub fn new() -> Reaper<K> {
+    let (sender, receiver) = channel();
+    Reaper { sender, receiver }
+  }
+
+  /// Take the ownership of the child, its exit will be received with `key`.
+  pub fn watch(&self, key: K, mut child: Child) {
+    let sender = self.sender.clone();
+    thread::spawn(move || {
+      let _ = sender.send(Event::Exit(key, child.wait()));
+    });
+  }
+
+  /// Receive the exit of a child which could not be spawned.
+  pub fn exited(&self, key: K, status: ExitStatus) {
+    let _ = self.sender.send(Event::Exit(key, Ok(status)));
+  }
+
+  /// Receive SIGINT and SIGTERM as events instead of being stopped, until the result is dropped.
+  pub fn catch_stop_signals(&self) -> io::Result<StopSignals> {
+    let sender = self.sender.clone();
+    StopSignals::new(move |signal| {
+      let _ = sender.send(Event::Signal(signal));
+    })
+  }
+
+  /// Block until the next event, or until `until` is reached.
+  pub fn next(&self, until: Option<Instant>) -> Option<Event<K>> {
+    match until {
+      Some(until) => match self
+        .receiver
+        .recv_timeout(until.saturating_duration_since(Instant::now()))
+      {
+        Ok(event) => Some(event),
+        Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+      },
+      None => self.receiver.recv().ok(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::process::Command;
+  use std::time::Duration;
+
+  fn exit(event: Option<Event<i32>>) -> (i32, bool) {
+    match event {
+      Some(Event::Exit(key, status)) => (key, status.unwrap().success()),
+      event => panic!("Unexpected event {:?}", event),
+    }
+  }
+
+  #[test]
+  fn next() {
+    let reaper = Reaper::new();
+    let started = Instant::now();
+    reaper.watch(1, Command::new("sleep").arg("0.2").spawn().unwrap());
+    reaper.watch(2, Command::new("false").spawn().unwrap());
+
+    assert_eq!(
+      exit(reaper.next(Some(started + Duration::from_secs(5)))),
+      (2, false)
+    );
+    assert!(reaper.next(Some(Instant::now())).is_none());
+    assert_eq!(exit(reaper.next(None)), (1, true));
+    reaper.exited(3, std::os::unix::process::ExitStatusExt::from_raw(127 << 8));
+    assert_eq!(exit(reaper.next(None)), (3, false));
+    // The exit is received right away, without waiting for a polling interval
+    assert!(started.elapsed() < Duration::from_secs(1));
+  }
+}
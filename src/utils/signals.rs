@@ -0,0 +1,60 @@
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::{Handle, Signals};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+
+/// Signals stopping runtasktic, forwarded to the tasks while they are running.
+pub const STOP_SIGNALS: [i32; 2] = [SIGINT, SIGTERM];
+
+static DEFAULT_ACTION: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Catch SIGINT and SIGTERM while it lives, `on_signal` is called from a thread for each of them.
+/// Their default action, stopping runtasktic, is restored when it is dropped.
+pub struct StopSignals {
+  handle: Handle,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl StopSignals {
+  pub fn new<F: FnMut(i32) + Send + 'static>(mut on_signal: F) -> io::Result<StopSignals> {
+    default_action()?.store(false, Ordering::SeqCst);
+    let mut signals = Signals::new(STOP_SIGNALS)?;
+    let handle = signals.handle();
+    let thread = thread::spawn(move || {
+      for signal in signals.forever() {
+        on_signal(signal);
+      }
+    });
+    Ok(StopSignals {
+      handle,
+      thread: Some(thread),
+    })
+  }
+}
+
+impl Drop for StopSignals {
+  fn drop(&mut self) {
+    self.handle.close();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+    if let Some(default) = DEFAULT_ACTION.get() {
+      default.store(true, Ordering::SeqCst);
+    }
+  }
+}
+
+/// Once a signal is caught, its default action is never called again by the system. This flag
+/// emulates it when no `StopSignals` is alive.
+fn default_action() -> io::Result<&'static Arc<AtomicBool>> {
+  if let Some(default) = DEFAULT_ACTION.get() {
+    return Ok(default);
+  }
+  let default = Arc::new(AtomicBool::new(true));
+  for signal in STOP_SIGNALS {
+    signal_hook::flag::register_conditional_default(signal, default.clone())?;
+  }
+  Ok(DEFAULT_ACTION.get_or_init(|| default))
+}
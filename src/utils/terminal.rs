@@ -0,0 +1,21 @@
+use std::io;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+/// Make the process group of the caller the foreground group of the terminal of the standard
+/// input, its processes can then read it. SIGTTOU is blocked meanwhile, it stops a background
+/// group changing the foreground one. Only async-signal-safe calls are made, it can be called
+/// between fork and exec.
+pub fn foreground() -> io::Result<()> {
+  unsafe {
+    let mut ttou = MaybeUninit::<libc::sigset_t>::uninit();
+    let mut previous = MaybeUninit::<libc::sigset_t>::uninit();
+    libc::sigemptyset(ttou.as_mut_ptr());
+    libc::sigaddset(ttou.as_mut_ptr(), libc::SIGTTOU);
+    libc::pthread_sigmask(libc::SIG_BLOCK, ttou.as_ptr(), previous.as_mut_ptr());
+    let result = libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+    let error = io::Error::last_os_error();
+    libc::pthread_sigmask(libc::SIG_SETMASK, previous.as_ptr(), ptr::null_mut());
+    if result == 0 { Ok(()) } else { Err(error) }
+  }
+}
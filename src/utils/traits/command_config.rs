@@ -1,5 +1,8 @@
-use anyhow::{Context, Result, ensure};
-use std::fs::{File, OpenOptions};
+use crate::config::{Config, Task};
+use crate::utils::terminal;
+use anyhow::{Context, Result, bail, ensure};
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::process::{Command, Stdio};
 
@@ -7,6 +10,9 @@ pub trait CommandConfig {
   fn working_dir(&mut self, dir: &Option<String>) -> Result<&mut Self>;
   fn stdout_opt(&mut self, stdout: &Option<String>, inherit: bool) -> Result<&mut Self>;
   fn stderr_opt(&mut self, stdout: &Option<String>, inherit: bool) -> Result<&mut Self>;
+  fn env_file(&mut self, file: &Option<String>) -> Result<&mut Self>;
+  fn task_env(&mut self, config: &Config, task: &Task) -> Result<&mut Self>;
+  fn task_group(&mut self, foreground: bool) -> &mut Self;
 }
 
 impl CommandConfig for Command {
@@ -34,6 +40,70 @@ impl CommandConfig for Command {
 
     Ok(self.stderr(stdio))
   }
+
+  fn env_file(&mut self, file: &Option<String>) -> Result<&mut Self> {
+    if let Some(f) = file {
+      let content =
+        fs::read_to_string(f).with_context(|| format!("Can't read the env file {}", f))?;
+      let env = parse_env_file(&content).with_context(|| format!("Invalid env file {}", f))?;
+      Ok(self.envs(env))
+    } else {
+      Ok(self)
+    }
+  }
+
+  /// The environment of the task: the env file, then the global `env` and the task `env`.
+  fn task_env(&mut self, config: &Config, task: &Task) -> Result<&mut Self> {
+    Ok(
+      self
+        .env_file(config.env_file())?
+        .envs(config.env())
+        .envs(task.env()),
+    )
+  }
+
+  /// Spawn the command in its own process group to signal it with all its children. With
+  /// `foreground`, the group takes the terminal before the program starts, a background group
+  /// reading it would be stopped by SIGTTIN.
+  fn task_group(&mut self, foreground: bool) -> &mut Self {
+    self.process_group(0);
+    if foreground {
+      unsafe {
+        self.pre_exec(|| {
+          // Without terminal, the program runs like in any other group
+          let _ = terminal::foreground();
+          Ok(())
+        })
+      };
+    }
+    self
+  }
+}
+
+/// Parse a dotenv file: `KEY=value` lines, with optional `export` prefix, quotes and comments.
+fn parse_env_file(content: &str) -> Result<Vec<(String, String)>> {
+  let mut env = vec![];
+  for (i, line) in content.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let Some((key, value)) = line.split_once('=') else {
+      bail!("Missing `=` on line {}", i + 1);
+    };
+    let value = value.trim();
+    let value = if value.len() >= 2
+      && ((value.starts_with('"') && value.ends_with('"'))
+        || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+      &value[1..value.len() - 1]
+    } else {
+      value
+    };
+    env.push((key.trim().to_string(), value.to_string()));
+  }
+  Ok(env)
 }
 
 fn process_stdio(
@@ -56,9 +126,87 @@ fn process_stdio(
 }
 
 fn open_options<P: AsRef<Path>>(file: P) -> Result<File> {
+  if let Some(parent) = file.as_ref().parent()
+    && !parent.as_os_str().is_empty()
+  {
+    fs::create_dir_all(parent)
+      .with_context(|| format!("Can't create the output directory {}", parent.display()))?;
+  }
   OpenOptions::new()
     .create(true)
     .append(true)
     .open(file.as_ref())
     .with_context(|| format!("Can't open output file {}", file.as_ref().display()))
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::deadline::Deadline;
+  use std::io::{BufRead, BufReader};
+  use std::time::{Duration, Instant};
+
+  #[test]
+  fn task_group() -> Result<()> {
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg("sleep 30 & echo $!; wait")
+      .stdout(Stdio::piped())
+      .task_group(false)
+      .spawn()?;
+    assert_ne!(unsafe { libc::getpgid(child.id() as i32) }, unsafe {
+      libc::getpgrp()
+    });
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line)?;
+    let grandchild = line.trim().to_string();
+
+    let mut deadline = Deadline::new(Some(Duration::from_millis(100)), Duration::from_millis(100));
+    assert!(!deadline.wait(child)?.success());
+    assert!(deadline.timed_out());
+
+    // The grandchild is killed with the group, at most a zombie waits for its new parent
+    let started = Instant::now();
+    let running = || {
+      fs::read_to_string(format!("/proc/{}/stat", grandchild)).is_ok_and(|stat| {
+        stat
+          .rsplit(')')
+          .next()
+          .is_some_and(|s| !s.starts_with(" Z"))
+      })
+    };
+    while running() && started.elapsed() < Duration::from_secs(5) {
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!running());
+    Ok(())
+  }
+
+  #[test]
+  fn parse_env_file() -> Result<()> {
+    let env = super::parse_env_file(
+      "
+# Comment
+KEY=value
+export EXPORTED = exported value
+DOUBLE=\"double quotes\"
+SINGLE='single quotes'
+EMPTY=
+URL=https://example.com/?a=b
+",
+    )?;
+    assert_eq!(
+      env,
+      vec![
+        ("KEY".to_string(), "value".to_string()),
+        ("EXPORTED".to_string(), "exported value".to_string()),
+        ("DOUBLE".to_string(), "double quotes".to_string()),
+        ("SINGLE".to_string(), "single quotes".to_string()),
+        ("EMPTY".to_string(), "".to_string()),
+        ("URL".to_string(), "https://example.com/?a=b".to_string()),
+      ]
+    );
+    assert!(super::parse_env_file("KEY").is_err());
+    Ok(())
+  }
+}